@@ -0,0 +1,375 @@
+//! JSON form of the syntax tree, for tools that don't link against the crate.
+//!
+//! ```text
+//! { "version": 1, "statements": [Stmt], "locals": [{ "id": number, "depth": number }] }
+//! ```
+//!
+//! Every statement and expression is an object whose `"type"` is the name of its variant,
+//! with the struct fields in snake case. Expressions also carry an `"id"`, numbering them from
+//! 1 in the order they are written, which is what `locals` refers to: the number of scopes
+//! between a variable use and its declaration, as computed by the resolver. Tokens are written as
+//! `{ "type", "lexeme", "line", "column" }`, plus `"literal"` for strings and numbers.
+
+use crate::{
+    expr::{Expr, HashExpr},
+    json::Json,
+    object::Object,
+    stmt::{FunctionStmt, Stmt},
+    token::Token,
+};
+use std::{collections::HashMap, rc::Rc};
+
+pub const VERSION: f64 = 1.0;
+
+pub fn to_json(statements: &[Rc<Stmt>], locals: Option<&HashMap<HashExpr, usize>>) -> Json {
+    let mut numbering = Numbering::default();
+    let mut fields = vec![
+        ("version", Json::Number(VERSION)),
+        ("statements", numbering.stmts(statements)),
+    ];
+
+    if let Some(locals) = locals {
+        let mut locals: Vec<(usize, usize)> = locals
+            .iter()
+            .filter_map(|(k, v)| numbering.ids.get(&k.id).map(|id| (*id, *v)))
+            .collect();
+        locals.sort();
+        let locals = locals
+            .into_iter()
+            .map(|(id, depth)| {
+                Json::object(vec![
+                    ("id", Json::Number(id as f64)),
+                    ("depth", Json::Number(depth as f64)),
+                ])
+            })
+            .collect();
+        fields.push(("locals", Json::Array(locals)));
+    }
+
+    Json::object(fields)
+}
+
+pub fn token(token: &Token) -> Json {
+    let mut fields = vec![
+        ("type", Json::string(format!("{:?}", token.token_type))),
+        ("lexeme", Json::string(token.lexeme.clone())),
+        ("line", Json::Number(token.line as f64)),
+        ("column", Json::Number(token.column as f64)),
+    ];
+    if token.literal != Object::Nil {
+        fields.push(("literal", value(&token.literal)));
+    }
+    Json::object(fields)
+}
+
+fn tokens(tokens: &[Rc<Token>]) -> Json {
+    Json::Array(tokens.iter().map(|x| token(x)).collect())
+}
+
+fn optional<T>(value: &Option<T>, f: impl FnOnce(&T) -> Json) -> Json {
+    value.as_ref().map_or(Json::Null, f)
+}
+
+/// Integers are written as `{"integer": "digits"}`: JSON numbers are read as doubles, which
+/// would round those above 2^53 and lose that they are integers.
+fn value(value: &Object) -> Json {
+    match value {
+        Object::Nil => Json::Null,
+        Object::Boolean(b) => Json::Bool(*b),
+        Object::Number(n) => Json::Number(*n),
+        Object::Integer(n) => Json::object(vec![("integer", Json::string(n.to_string()))]),
+        Object::String(s) => Json::string(s.clone()),
+        // Literals never hold runtime objects.
+        value => Json::string(value.to_string()),
+    }
+}
+
+fn node(kind: &str, mut fields: Vec<(&str, Json)>) -> Json {
+    fields.insert(0, ("type", Json::string(kind)));
+    Json::object(fields)
+}
+
+/// Numbers expressions in the order they are written, so that the ids of a tree only depend on
+/// its source.
+#[derive(Default)]
+struct Numbering {
+    ids: HashMap<usize, usize>,
+}
+
+impl Numbering {
+    fn number(&mut self, hash_expr: &HashExpr) -> usize {
+        let next = self.ids.len() + 1;
+        *self.ids.entry(hash_expr.id).or_insert(next)
+    }
+
+    fn stmts(&mut self, statements: &[Rc<Stmt>]) -> Json {
+        Json::Array(statements.iter().map(|x| self.stmt(x)).collect())
+    }
+
+    fn function(&mut self, stmt: &FunctionStmt) -> Json {
+        node(
+            "Function",
+            vec![
+                ("name", token(&stmt.name)),
+                ("params", tokens(&stmt.params)),
+                ("body", self.stmts(&stmt.body)),
+            ],
+        )
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> Json {
+        match stmt {
+            Stmt::Expression(stmt) => node(
+                "Expression",
+                vec![("expression", self.expr(&stmt.expression))],
+            ),
+            Stmt::Print(stmt) => node(
+                "Print",
+                vec![
+                    ("keyword", token(&stmt.keyword)),
+                    ("expression", self.expr(&stmt.expression)),
+                ],
+            ),
+            Stmt::Var(stmt) => node(
+                "Var",
+                vec![
+                    ("keyword", token(&stmt.keyword)),
+                    ("name", token(&stmt.name)),
+                    ("initializer", optional(&stmt.initializer, |x| self.expr(x))),
+                ],
+            ),
+            Stmt::Destructure(stmt) => node(
+                "Destructure",
+                vec![
+                    ("keyword", token(&stmt.keyword)),
+                    ("pattern", token(&stmt.pattern)),
+                    ("names", tokens(&stmt.names)),
+                    ("initializer", self.expr(&stmt.initializer)),
+                ],
+            ),
+            Stmt::Block(stmt) => node("Block", vec![("statements", self.stmts(&stmt.statements))]),
+            Stmt::If(stmt) => node(
+                "If",
+                vec![
+                    ("condition", self.expr(&stmt.condition)),
+                    ("then_branch", self.stmt(&stmt.then_branch)),
+                    ("else_branch", optional(&stmt.else_branch, |x| self.stmt(x))),
+                ],
+            ),
+            Stmt::While(stmt) => node(
+                "While",
+                vec![
+                    ("condition", self.expr(&stmt.condition)),
+                    ("body", self.stmt(&stmt.body)),
+                ],
+            ),
+            Stmt::Function(stmt) => self.function(stmt),
+            Stmt::Return(stmt) => node(
+                "Return",
+                vec![
+                    ("keyword", token(&stmt.keyword)),
+                    ("value", optional(&stmt.value, |x| self.expr(x))),
+                ],
+            ),
+            Stmt::Class(stmt) => node(
+                "Class",
+                vec![
+                    ("name", token(&stmt.name)),
+                    ("superclass", optional(&stmt.superclass, |x| self.expr(x))),
+                    (
+                        "traits",
+                        Json::Array(stmt.traits.iter().map(|x| self.expr(x)).collect()),
+                    ),
+                    (
+                        "methods",
+                        Json::Array(stmt.methods.iter().map(|x| self.function(x)).collect()),
+                    ),
+                ],
+            ),
+            Stmt::Trait(stmt) => node(
+                "Trait",
+                vec![
+                    ("name", token(&stmt.name)),
+                    (
+                        "methods",
+                        Json::Array(stmt.methods.iter().map(|x| self.function(x)).collect()),
+                    ),
+                ],
+            ),
+            Stmt::Throw(stmt) => node(
+                "Throw",
+                vec![
+                    ("keyword", token(&stmt.keyword)),
+                    ("value", self.expr(&stmt.value)),
+                ],
+            ),
+            Stmt::Try(stmt) => node(
+                "Try",
+                vec![
+                    ("keyword", token(&stmt.keyword)),
+                    ("body", self.stmts(&stmt.body)),
+                    (
+                        "handler",
+                        optional(&stmt.handler, |x| {
+                            Json::object(vec![
+                                ("name", token(&x.name)),
+                                ("body", self.stmts(&x.body)),
+                            ])
+                        }),
+                    ),
+                    ("finally", optional(&stmt.finally, |x| self.stmts(x))),
+                ],
+            ),
+            Stmt::Import(stmt) => node(
+                "Import",
+                vec![
+                    ("keyword", token(&stmt.keyword)),
+                    ("path", token(&stmt.path)),
+                    ("name", optional(&stmt.name, |x| token(x))),
+                    ("names", tokens(&stmt.names)),
+                ],
+            ),
+            Stmt::Test(stmt) => node(
+                "Test",
+                vec![
+                    ("keyword", token(&stmt.keyword)),
+                    ("name", token(&stmt.name)),
+                    ("body", self.stmts(&stmt.body)),
+                ],
+            ),
+        }
+    }
+
+    fn expr(&mut self, hash_expr: &HashExpr) -> Json {
+        let id = self.number(hash_expr);
+        let (kind, fields) = match &hash_expr.expr {
+            Expr::Binary(expr) => (
+                "Binary",
+                vec![
+                    ("left", self.expr(&expr.left)),
+                    ("op", token(&expr.op)),
+                    ("right", self.expr(&expr.right)),
+                ],
+            ),
+            Expr::Grouping(expr) => (
+                "Grouping",
+                vec![("expression", self.expr(&expr.expression))],
+            ),
+            Expr::Literal(expr) => ("Literal", vec![("value", value(&expr.value))]),
+            Expr::Unary(expr) => (
+                "Unary",
+                vec![("op", token(&expr.op)), ("right", self.expr(&expr.right))],
+            ),
+            Expr::Variable(expr) => ("Variable", vec![("name", token(&expr.name))]),
+            Expr::Assign(expr) => (
+                "Assign",
+                vec![
+                    ("name", token(&expr.name)),
+                    ("value", self.expr(&expr.value)),
+                ],
+            ),
+            Expr::AssignList(expr) => (
+                "AssignList",
+                vec![
+                    ("bracket", token(&expr.bracket)),
+                    (
+                        "targets",
+                        Json::Array(expr.targets.iter().map(|x| self.expr(x)).collect()),
+                    ),
+                    ("value", self.expr(&expr.value)),
+                ],
+            ),
+            Expr::Logical(expr) => (
+                "Logical",
+                vec![
+                    ("left", self.expr(&expr.left)),
+                    ("op", token(&expr.op)),
+                    ("right", self.expr(&expr.right)),
+                ],
+            ),
+            Expr::Call(expr) => (
+                "Call",
+                vec![
+                    ("callee", self.expr(&expr.callee)),
+                    ("paren", token(&expr.paren)),
+                    (
+                        "arguments",
+                        Json::Array(expr.arguments.iter().map(|x| self.expr(x)).collect()),
+                    ),
+                ],
+            ),
+            Expr::Get(expr) => (
+                "Get",
+                vec![
+                    ("object", self.expr(&expr.object)),
+                    ("name", token(&expr.name)),
+                    ("optional", Json::Bool(expr.optional)),
+                ],
+            ),
+            Expr::Set(expr) => (
+                "Set",
+                vec![
+                    ("object", self.expr(&expr.object)),
+                    ("name", token(&expr.name)),
+                    ("value", self.expr(&expr.value)),
+                ],
+            ),
+            Expr::This(expr) => ("This", vec![("keyword", token(&expr.keyword))]),
+            Expr::Super(expr) => (
+                "Super",
+                vec![
+                    ("keyword", token(&expr.keyword)),
+                    ("method", token(&expr.method)),
+                ],
+            ),
+            Expr::List(expr) => (
+                "List",
+                vec![
+                    ("bracket", token(&expr.bracket)),
+                    (
+                        "elements",
+                        Json::Array(expr.elements.iter().map(|x| self.expr(x)).collect()),
+                    ),
+                ],
+            ),
+            Expr::Index(expr) => (
+                "Index",
+                vec![
+                    ("object", self.expr(&expr.object)),
+                    ("bracket", token(&expr.bracket)),
+                    ("index", self.expr(&expr.index)),
+                ],
+            ),
+            Expr::SetIndex(expr) => (
+                "SetIndex",
+                vec![
+                    ("object", self.expr(&expr.object)),
+                    ("bracket", token(&expr.bracket)),
+                    ("index", self.expr(&expr.index)),
+                    ("value", self.expr(&expr.value)),
+                ],
+            ),
+            Expr::Conditional(expr) => (
+                "Conditional",
+                vec![
+                    ("condition", self.expr(&expr.condition)),
+                    ("then_branch", self.expr(&expr.then_branch)),
+                    ("else_branch", self.expr(&expr.else_branch)),
+                ],
+            ),
+            Expr::Update(expr) => (
+                "Update",
+                vec![
+                    ("target", self.expr(&expr.target)),
+                    ("op", token(&expr.op)),
+                    ("value", optional(&expr.value, |x| self.expr(x))),
+                    ("prefix", Json::Bool(expr.prefix)),
+                ],
+            ),
+        };
+
+        let mut fields = fields;
+        fields.insert(0, ("id", Json::Number(id as f64)));
+        node(kind, fields)
+    }
+}
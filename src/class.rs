@@ -15,6 +15,8 @@ use std::{
 
 pub trait IsClass: fmt::Debug + fmt::Display + IsFunction + IsInstance {
     fn find_method(&self, name: &str) -> Option<Rc<Function>>;
+    fn superclass(&self) -> Option<Rc<RefCell<dyn IsClass>>>;
+    fn method_names(&self) -> Vec<String>;
 }
 
 #[derive(Debug)]
@@ -83,7 +85,7 @@ impl Stateful for Class {
             return Ok(value);
         }
 
-        Err(InterpretError::Error(format!(
+        Err(InterpretError::new(format!(
             "Undefined property `{}`.",
             key,
         )))
@@ -101,7 +103,11 @@ impl fmt::Display for Class {
     }
 }
 
-impl IsInstance for Class {}
+impl IsInstance for Class {
+    fn field_names(&self) -> Vec<String> {
+        self.fields.keys().cloned().collect()
+    }
+}
 
 impl IsClass for Class {
     fn find_method(&self, key: &str) -> Option<Rc<Function>> {
@@ -115,4 +121,12 @@ impl IsClass for Class {
 
         None
     }
+
+    fn superclass(&self) -> Option<Rc<RefCell<dyn IsClass>>> {
+        self.superclass.clone()
+    }
+
+    fn method_names(&self) -> Vec<String> {
+        self.methods.keys().cloned().collect()
+    }
 }
@@ -0,0 +1,143 @@
+use crate::{
+    interpreter::{Hook, InterpretError, Interpreter},
+    parser::parse,
+    scanner::scan_tokens,
+    stmt::Stmt,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    rc::Rc,
+    slice,
+};
+
+/// Hits per line of each file that was run, every line with a statement included.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Coverage {
+    pub files: BTreeMap<String, BTreeMap<usize, usize>>,
+}
+
+impl Coverage {
+    pub fn new() -> Coverage {
+        Coverage::default()
+    }
+
+    /// Add a run of `code`, read from `file`, in which `recorder` watched the statements.
+    pub fn add(&mut self, file: &str, code: &str, recorder: &Recorder) -> Result<(), String> {
+        let lines = self.files.entry(file.to_string()).or_default();
+        for line in executable_lines(code)? {
+            lines.entry(line).or_default();
+        }
+        for (line, hits) in &recorder.lines {
+            *lines.entry(*line).or_default() += hits;
+        }
+        Ok(())
+    }
+
+    pub fn merge(&mut self, other: &Coverage) {
+        for (file, other_lines) in &other.files {
+            let lines = self.files.entry(file.clone()).or_default();
+            for (line, hits) in other_lines {
+                *lines.entry(*line).or_default() += hits;
+            }
+        }
+    }
+
+    /// Read a report written by `to_lcov`. Records other than line data are skipped.
+    pub fn from_lcov(text: &str) -> Result<Coverage, String> {
+        let mut coverage = Coverage::new();
+        let mut file: Option<String> = None;
+
+        for (i, line) in text.lines().enumerate() {
+            let error = || format!("[line {}] : Invalid lcov record `{}`.", i + 1, line);
+            let line = line.trim();
+            if let Some(name) = line.strip_prefix("SF:") {
+                coverage.files.entry(name.to_string()).or_default();
+                file = Some(name.to_string());
+            } else if let Some(data) = line.strip_prefix("DA:") {
+                let mut fields = data.split(',');
+                let number = fields.next().and_then(|x| x.parse::<usize>().ok());
+                let hits = fields.next().and_then(|x| x.parse::<usize>().ok());
+                let (Some(name), Some(number), Some(hits)) = (&file, number, hits) else {
+                    return Err(error());
+                };
+                let lines = coverage.files.entry(name.clone()).or_default();
+                *lines.entry(number).or_default() += hits;
+            } else if line == "end_of_record" {
+                file = None;
+            }
+        }
+
+        Ok(coverage)
+    }
+
+    pub fn to_lcov(&self) -> String {
+        let mut out = String::new();
+        for (file, lines) in &self.files {
+            let _ = writeln!(out, "TN:\nSF:{}", file);
+            for (line, hits) in lines {
+                let _ = writeln!(out, "DA:{},{}", line, hits);
+            }
+            let hit = lines.values().filter(|x| **x > 0).count();
+            let _ = writeln!(out, "LF:{}\nLH:{}\nend_of_record", lines.len(), hit);
+        }
+        out
+    }
+}
+
+/// Lines that start a statement, the ones coverage is reported for.
+pub fn executable_lines(code: &str) -> Result<BTreeSet<usize>, String> {
+    fn walk(statements: &[Rc<Stmt>], lines: &mut BTreeSet<usize>) {
+        for statement in statements {
+            lines.extend(statement.line());
+            match statement.as_ref() {
+                Stmt::Block(stmt) => walk(&stmt.statements, lines),
+                Stmt::If(stmt) => {
+                    walk(slice::from_ref(&stmt.then_branch), lines);
+                    walk(stmt.else_branch.as_slice(), lines);
+                }
+                Stmt::While(stmt) => walk(slice::from_ref(&stmt.body), lines),
+                Stmt::Function(stmt) => walk(&stmt.body, lines),
+                Stmt::Class(stmt) => stmt.methods.iter().for_each(|x| walk(&x.body, lines)),
+                Stmt::Trait(stmt) => stmt.methods.iter().for_each(|x| walk(&x.body, lines)),
+                Stmt::Test(stmt) => walk(&stmt.body, lines),
+                Stmt::Try(stmt) => {
+                    walk(&stmt.body, lines);
+                    if let Some(ref handler) = stmt.handler {
+                        walk(&handler.body, lines);
+                    }
+                    if let Some(ref finally) = stmt.finally {
+                        walk(finally, lines);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let statements = parse(scan_tokens(code)?)?;
+    let mut lines = BTreeSet::new();
+    walk(&statements, &mut lines);
+    Ok(lines)
+}
+
+/// Counts the statements run on each line of a script, leaving out imported modules.
+#[derive(Default)]
+pub struct Recorder {
+    pub lines: BTreeMap<usize, usize>,
+}
+
+impl Recorder {
+    pub fn new() -> Recorder {
+        Recorder::default()
+    }
+}
+
+impl Hook for Recorder {
+    fn statement(&mut self, interpreter: &Interpreter, line: usize) -> Result<(), InterpretError> {
+        if interpreter.in_script() {
+            *self.lines.entry(line).or_default() += 1;
+        }
+        Ok(())
+    }
+}
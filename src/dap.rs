@@ -0,0 +1,507 @@
+//! A debug adapter speaking the Debug Adapter Protocol over stdio, for debugging scripts
+//! from an editor. There is a single thread, and requests are only read while the script is
+//! stopped, so `pause` is refused.
+
+use crate::{
+    debugger::{describe, lookup, members, scopes, Step, Tracker},
+    interpreter::{Hook, InterpretError, Interpreter},
+    json::Json,
+    lox::{skip_out, Lox, LoxError},
+    lsp::{read_message, write_message},
+    object::Object,
+    token::Token,
+};
+use std::{
+    cell::RefCell,
+    fs,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+const THREAD: f64 = 1.0;
+
+/// The connection to the client, shared by the adapter and the script's output.
+struct Connection {
+    output: Box<dyn Write>,
+    seq: usize,
+}
+
+impl Connection {
+    fn send(&mut self, kind: &str, mut fields: Vec<(&str, Json)>) {
+        self.seq += 1;
+        fields.insert(0, ("seq", Json::Number(self.seq as f64)));
+        fields.insert(1, ("type", Json::string(kind)));
+        let _ = write_message(&mut self.output, &Json::object(fields));
+    }
+
+    fn event(&mut self, event: &str, body: Json) {
+        self.send(
+            "event",
+            vec![("event", Json::string(event)), ("body", body)],
+        );
+    }
+}
+
+/// What the script prints, forwarded as `output` events.
+struct ProgramOutput(Rc<RefCell<Connection>>);
+
+impl Write for ProgramOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        let body = Json::object(vec![
+            ("category", Json::string("stdout")),
+            ("output", Json::string(text)),
+        ]);
+        self.0.borrow_mut().event("output", body);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Something the client can expand: a scope or a value with members.
+enum Variables {
+    Scope(Vec<(String, Object)>),
+    Value(Object),
+}
+
+enum Reply {
+    Done(Json),
+    Failed(String),
+}
+
+struct Session {
+    input: Box<dyn BufRead>,
+    connection: Rc<RefCell<Connection>>,
+    tracker: Tracker,
+    program: Option<PathBuf>,
+    args: Vec<String>,
+    // Indexed by `variablesReference - 1`, valid until the script resumes.
+    variables: Vec<Variables>,
+    started: bool,
+    resumed: bool,
+    disconnected: bool,
+}
+
+/// Serve debug requests from `input` until the client disconnects.
+pub fn serve(input: impl BufRead + 'static, output: impl Write + 'static) -> io::Result<()> {
+    let connection = Rc::new(RefCell::new(Connection {
+        output: Box::new(output),
+        seq: 0,
+    }));
+    let session = Rc::new(RefCell::new(Session {
+        input: Box::new(input),
+        connection: connection.clone(),
+        tracker: Tracker::new(),
+        program: None,
+        args: Vec::new(),
+        variables: Vec::new(),
+        started: false,
+        resumed: false,
+        disconnected: false,
+    }));
+
+    // Configuration: everything up to `configurationDone`.
+    while !session.borrow().started {
+        if !session.borrow_mut().next_request(false)? {
+            return Ok(());
+        }
+    }
+
+    let (program, args) = {
+        let session = session.borrow();
+        (session.program.clone(), session.args.clone())
+    };
+    let result = match program {
+        Some(ref program) => run(program, args, session.clone(), connection.clone()),
+        None => Err(LoxError::Compile(
+            "Nothing to debug, `launch` was not requested.".to_string(),
+        )),
+    };
+    if session.borrow().disconnected {
+        return Ok(());
+    }
+
+    let code = match result {
+        Ok(()) => 0,
+        Err(err) => {
+            let body = Json::object(vec![
+                ("category", Json::string("stderr")),
+                ("output", Json::string(format!("{}\n", err))),
+            ]);
+            connection.borrow_mut().event("output", body);
+            err.exit_code()
+        }
+    };
+    let mut connection = connection.borrow_mut();
+    connection.event(
+        "exited",
+        Json::object(vec![("exitCode", Json::Number(code as f64))]),
+    );
+    connection.event("terminated", Json::object(vec![]));
+    drop(connection);
+
+    while session.borrow_mut().next_request(false)? {}
+    Ok(())
+}
+
+fn run(
+    program: &Path,
+    args: Vec<String>,
+    session: Rc<RefCell<Session>>,
+    connection: Rc<RefCell<Connection>>,
+) -> Result<(), LoxError> {
+    let code = fs::read_to_string(program)
+        .map_err(|e| LoxError::Compile(format!("Can't read `{}`: {}", program.display(), e)))?;
+
+    let lox = Lox::new().search_paths_from_env().args(args);
+    let mut interpreter = lox.interpreter();
+    interpreter.set_hook(session);
+    interpreter.set_output(Rc::new(RefCell::new(ProgramOutput(connection))));
+    lox.run_with(&mut interpreter, skip_out(&code), Some(program))
+}
+
+fn number(json: &Json, key: &str) -> Option<usize> {
+    json.get(key).and_then(Json::as_f64).map(|x| x as usize)
+}
+
+impl Session {
+    /// Handle one request. Returns false once the client has gone.
+    fn next_request(&mut self, paused: bool) -> io::Result<bool> {
+        let Some(request) = read_message(&mut self.input)? else {
+            self.disconnected = true;
+            return Ok(false);
+        };
+
+        let command = request
+            .get("command")
+            .and_then(Json::as_str)
+            .unwrap_or("")
+            .to_string();
+        let arguments = request.get("arguments").cloned().unwrap_or(Json::Null);
+
+        let reply = self.handle(&command, &arguments, paused);
+        let mut fields = vec![
+            (
+                "request_seq",
+                request.get("seq").cloned().unwrap_or(Json::Null),
+            ),
+            ("command", Json::string(command.clone())),
+        ];
+        match reply {
+            Reply::Done(body) => {
+                fields.push(("success", Json::Bool(true)));
+                fields.push(("body", body));
+            }
+            Reply::Failed(message) => {
+                fields.push(("success", Json::Bool(false)));
+                fields.push(("message", Json::string(message)));
+            }
+        }
+        self.connection.borrow_mut().send("response", fields);
+
+        match command.as_str() {
+            "initialize" => self.event("initialized", Json::object(vec![])),
+            "disconnect" | "terminate" => self.disconnected = true,
+            _ => {}
+        }
+        Ok(!self.disconnected)
+    }
+
+    fn event(&mut self, event: &str, body: Json) {
+        self.connection.borrow_mut().event(event, body);
+    }
+
+    fn handle(&mut self, command: &str, arguments: &Json, paused: bool) -> Reply {
+        let empty = Json::object(vec![]);
+        match command {
+            "initialize" => Reply::Done(Json::object(vec![
+                ("supportsConfigurationDoneRequest", Json::Bool(true)),
+                ("supportsEvaluateForHovers", Json::Bool(true)),
+            ])),
+            "launch" => {
+                let Some(program) = arguments.get("program").and_then(Json::as_str) else {
+                    return Reply::Failed("Missing `program` to launch.".to_string());
+                };
+                self.program = Some(PathBuf::from(program));
+                self.args = arguments
+                    .get("args")
+                    .and_then(Json::as_array)
+                    .map(|x| {
+                        x.iter()
+                            .filter_map(Json::as_str)
+                            .map(String::from)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                if arguments.get("stopOnEntry") != Some(&Json::Bool(true)) {
+                    self.tracker.step = Step::Continue;
+                }
+                Reply::Done(empty)
+            }
+            "setBreakpoints" => self.set_breakpoints(arguments),
+            "configurationDone" => {
+                self.started = true;
+                Reply::Done(empty)
+            }
+            "threads" => Reply::Done(Json::object(vec![(
+                "threads",
+                Json::Array(vec![Json::object(vec![
+                    ("id", Json::Number(THREAD)),
+                    ("name", Json::string("main")),
+                ])]),
+            )])),
+            "stackTrace" => Reply::Done(self.stack_trace()),
+            "scopes" => self.scopes(arguments),
+            "variables" => self.variables(arguments),
+            "evaluate" => self.evaluate(arguments),
+            "continue" | "next" | "stepIn" | "stepOut" if paused => {
+                self.tracker.resume(match command {
+                    "next" => Step::Over(0),
+                    "stepIn" => Step::Into,
+                    "stepOut" => Step::Out(0),
+                    _ => Step::Continue,
+                });
+                self.variables.clear();
+                self.resumed = true;
+                Reply::Done(Json::object(vec![(
+                    "allThreadsContinued",
+                    Json::Bool(true),
+                )]))
+            }
+            // Requests are only read while the script is stopped.
+            "pause" => Reply::Failed("Pausing a running script is not supported.".to_string()),
+            "disconnect" | "terminate" => Reply::Done(empty),
+            _ => Reply::Failed(format!("Unsupported command `{}`.", command)),
+        }
+    }
+
+    fn set_breakpoints(&mut self, arguments: &Json) -> Reply {
+        let path = arguments
+            .get("source")
+            .and_then(|x| x.get("path"))
+            .and_then(Json::as_str)
+            .map(PathBuf::from);
+        // Only the program itself can be stopped in.
+        let canonical = |x: &Path| x.canonicalize().ok();
+        let verified = match (&path, &self.program) {
+            (Some(path), Some(program)) => canonical(path) == canonical(program),
+            _ => false,
+        };
+
+        let lines: Vec<usize> = arguments
+            .get("breakpoints")
+            .and_then(Json::as_array)
+            .map(|x| x.iter().filter_map(|x| number(x, "line")).collect())
+            .unwrap_or_default();
+        if verified {
+            self.tracker.breakpoints = lines.iter().cloned().collect();
+        }
+
+        let breakpoints = lines
+            .iter()
+            .map(|x| {
+                Json::object(vec![
+                    ("verified", Json::Bool(verified)),
+                    ("line", Json::Number(*x as f64)),
+                ])
+            })
+            .collect();
+        Reply::Done(Json::object(vec![(
+            "breakpoints",
+            Json::Array(breakpoints),
+        )]))
+    }
+
+    fn stack_trace(&self) -> Json {
+        let source = self.program.as_ref().map_or(Json::Null, |x| {
+            Json::object(vec![
+                (
+                    "name",
+                    Json::string(x.file_name().unwrap_or_default().to_string_lossy()),
+                ),
+                ("path", Json::string(x.to_string_lossy())),
+            ])
+        });
+
+        // Frame ids count from the top of the stack.
+        let frames: Vec<Json> = self
+            .tracker
+            .frames
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(i, frame)| {
+                Json::object(vec![
+                    ("id", Json::Number(i as f64)),
+                    ("name", Json::string(frame.name.clone())),
+                    ("line", Json::Number(frame.line as f64)),
+                    ("column", Json::Number(1.0)),
+                    ("source", source.clone()),
+                ])
+            })
+            .collect();
+        Json::object(vec![
+            ("totalFrames", Json::Number(frames.len() as f64)),
+            ("stackFrames", Json::Array(frames)),
+        ])
+    }
+
+    fn reference(&mut self, variables: Variables) -> f64 {
+        self.variables.push(variables);
+        self.variables.len() as f64
+    }
+
+    fn frame(&self, arguments: &Json) -> Option<usize> {
+        let id = number(arguments, "frameId")?;
+        (id < self.tracker.frames.len()).then(|| self.tracker.frames.len() - 1 - id)
+    }
+
+    // One scope per `Environment` of the frame, innermost first.
+    fn scopes(&mut self, arguments: &Json) -> Reply {
+        let Some(frame) = self.frame(arguments) else {
+            return Reply::Failed("Unknown frame.".to_string());
+        };
+
+        let environment = self.tracker.frames[frame].environment.clone();
+        let levels = scopes(&environment);
+        let count = levels.len();
+        let scopes = levels
+            .into_iter()
+            .enumerate()
+            .map(|(i, values)| {
+                let name = match i {
+                    _ if i + 1 == count => "Globals".to_string(),
+                    0 => "Locals".to_string(),
+                    i => format!("Enclosing {}", i),
+                };
+                Json::object(vec![
+                    ("name", Json::string(name)),
+                    (
+                        "variablesReference",
+                        Json::Number(self.reference(Variables::Scope(values))),
+                    ),
+                    ("expensive", Json::Bool(false)),
+                ])
+            })
+            .collect();
+        Reply::Done(Json::object(vec![("scopes", Json::Array(scopes))]))
+    }
+
+    fn variable(&mut self, name: String, value: Object) -> Json {
+        let expandable = matches!(
+            value,
+            Object::Instance(_) | Object::Class(_) | Object::List(_) | Object::Module(_)
+        );
+        let text = describe(&value);
+        let reference = if expandable {
+            self.reference(Variables::Value(value))
+        } else {
+            0.0
+        };
+        Json::object(vec![
+            ("name", Json::string(name)),
+            ("value", Json::string(text)),
+            ("variablesReference", Json::Number(reference)),
+        ])
+    }
+
+    fn variables(&mut self, arguments: &Json) -> Reply {
+        let reference = number(arguments, "variablesReference").unwrap_or(0);
+        let values = match self.variables.get(reference.wrapping_sub(1)) {
+            Some(Variables::Scope(values)) => values.clone(),
+            Some(Variables::Value(value)) => members(value),
+            None => return Reply::Failed("Unknown variables reference.".to_string()),
+        };
+
+        let variables = values
+            .into_iter()
+            .map(|(name, value)| self.variable(name, value))
+            .collect();
+        Reply::Done(Json::object(vec![("variables", Json::Array(variables))]))
+    }
+
+    // Names and field paths only, `a.b.c`, looked up from the frame's innermost scope.
+    fn evaluate(&mut self, arguments: &Json) -> Reply {
+        let expression = arguments
+            .get("expression")
+            .and_then(Json::as_str)
+            .unwrap_or("");
+        let frame = self
+            .frame(arguments)
+            .or_else(|| self.tracker.frames.len().checked_sub(1));
+        let Some(frame) = frame else {
+            return Reply::Failed("The script isn't stopped.".to_string());
+        };
+
+        let environment = self.tracker.frames[frame].environment.clone();
+        match lookup(&environment, expression.trim()) {
+            Ok(value) => {
+                let variable = self.variable(String::new(), value);
+                Reply::Done(Json::object(vec![
+                    (
+                        "result",
+                        variable.get("value").cloned().unwrap_or(Json::Null),
+                    ),
+                    (
+                        "variablesReference",
+                        variable
+                            .get("variablesReference")
+                            .cloned()
+                            .unwrap_or(Json::Null),
+                    ),
+                ]))
+            }
+            Err(message) => Reply::Failed(message),
+        }
+    }
+}
+
+impl Hook for Session {
+    fn statement(&mut self, interpreter: &Interpreter, line: usize) -> Result<(), InterpretError> {
+        let step = self.tracker.step;
+        let first = self.tracker.frames.is_empty();
+        if !self.tracker.statement(interpreter, line) {
+            return Ok(());
+        }
+
+        let reason = match step {
+            Step::Into if first => "entry",
+            Step::Continue => "breakpoint",
+            _ => "step",
+        };
+        self.event(
+            "stopped",
+            Json::object(vec![
+                ("reason", Json::string(reason)),
+                ("threadId", Json::Number(THREAD)),
+                ("allThreadsStopped", Json::Bool(true)),
+            ]),
+        );
+
+        // Serve requests until one of them resumes the script.
+        self.resumed = false;
+        while !self.resumed {
+            match self.next_request(true) {
+                Ok(true) => {}
+                _ => {
+                    return Err(InterpretError::Abort(
+                        "Stopped by the debugger.".to_string(),
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn enter(&mut self, interpreter: &Interpreter, name: &Rc<Token>) {
+        self.tracker.enter(interpreter, name);
+    }
+
+    fn exit(&mut self, _: &Interpreter, _: bool) {
+        self.tracker.exit();
+    }
+}
@@ -0,0 +1,401 @@
+use crate::{
+    environment::{Environment, Stateful},
+    interpreter::{Hook, InterpretError, Interpreter},
+    object::Object,
+    token::Token,
+};
+use std::{
+    cell::RefCell,
+    collections::BTreeSet,
+    io::{BufRead, Write},
+    rc::Rc,
+};
+
+const HELP: &str = "Commands:
+  b, break LINE     Set a breakpoint, or list them without LINE
+  d, delete LINE    Remove a breakpoint
+  c, continue       Run until the next breakpoint
+  s, step           Run until the next statement, entering calls
+  n, next           Run until the next statement in this function or its callers
+  o, out            Run until the current function returns
+  bt, backtrace     Show the call stack
+  env               Show the variables of every enclosing scope
+  p, print NAME     Show a variable, `a.b` for a field, with its fields
+  l, list           Show the source around the current line
+  q, quit           Stop the program
+  h, help           Show this help";
+
+/// A function being run, the script itself at the bottom of the stack.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub name: String,
+    pub line: usize,
+    /// The innermost scope of the frame, updated before each of its statements.
+    pub environment: Rc<RefCell<Environment>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Step {
+    Continue,
+    Into,
+    /// Stop at a frame no deeper than this.
+    Over(usize),
+    /// Stop at a frame shallower than this.
+    Out(usize),
+}
+
+/// Follows the call stack of a running script and decides where to stop, for debugger
+/// front ends. Only the script is stopped in: code of imported modules runs through.
+#[derive(Debug)]
+pub struct Tracker {
+    pub frames: Vec<Frame>,
+    pub breakpoints: BTreeSet<usize>,
+    pub step: Step,
+    globals: Option<Rc<RefCell<Environment>>>,
+    // Depth and line of the last statement, so a line with several statements stops once.
+    last: Option<(usize, usize)>,
+}
+
+impl Default for Tracker {
+    fn default() -> Tracker {
+        Tracker::new()
+    }
+}
+
+impl Tracker {
+    /// The tracker stops before the first statement.
+    pub fn new() -> Tracker {
+        Tracker {
+            frames: Vec::new(),
+            breakpoints: BTreeSet::new(),
+            step: Step::Into,
+            globals: None,
+            last: None,
+        }
+    }
+
+    /// Record that a statement on `line` is about to run and tell whether to stop there.
+    pub fn statement(&mut self, interpreter: &Interpreter, line: usize) -> bool {
+        // The first statement seen belongs to the script.
+        let globals = interpreter.globals();
+        let script = self.globals.get_or_insert_with(|| globals.clone());
+        if !Rc::ptr_eq(script, &globals) {
+            return false;
+        }
+
+        if self.frames.is_empty() {
+            self.frames.push(Frame {
+                name: "<script>".to_string(),
+                line,
+                environment: interpreter.environment(),
+            });
+        }
+        if let Some(frame) = self.frames.last_mut() {
+            frame.line = line;
+            frame.environment = interpreter.environment();
+        }
+
+        let depth = self.frames.len();
+        let stop = match self.step {
+            Step::Continue => false,
+            Step::Into => true,
+            Step::Over(frame) => depth <= frame,
+            Step::Out(frame) => depth < frame,
+        } || (self.breakpoints.contains(&line) && self.last != Some((depth, line)));
+        self.last = Some((depth, line));
+
+        if stop {
+            self.step = Step::Continue;
+        }
+        stop
+    }
+
+    pub fn enter(&mut self, interpreter: &Interpreter, name: &Token) {
+        self.frames.push(Frame {
+            name: name.lexeme.clone(),
+            line: name.line,
+            environment: interpreter.environment(),
+        });
+    }
+
+    pub fn exit(&mut self) {
+        self.frames.pop();
+    }
+
+    pub fn resume(&mut self, step: Step) {
+        let depth = self.frames.len();
+        self.step = match step {
+            Step::Over(_) => Step::Over(depth),
+            Step::Out(_) => Step::Out(depth),
+            step => step,
+        };
+    }
+}
+
+/// A value as the debugger shows it: strings are quoted.
+pub fn describe(value: &Object) -> String {
+    match value {
+        Object::String(s) => format!("{:?}", s),
+        value => value.to_string(),
+    }
+}
+
+/// What a value holds: the fields of an instance, the methods and static fields of a class,
+/// the elements of a list or the globals of a module.
+pub fn members(value: &Object) -> Vec<(String, Object)> {
+    let lookup = |names: Vec<String>, state: &dyn Stateful| {
+        let mut names = names;
+        names.sort();
+        names
+            .into_iter()
+            .filter_map(|x| state.get(&x).ok().map(|value| (x, value)))
+            .collect::<Vec<_>>()
+    };
+
+    match value {
+        Object::Instance(instance) => {
+            let instance = instance.borrow();
+            lookup(instance.field_names(), &*instance)
+        }
+        Object::Class(class) => {
+            let class = class.borrow();
+            let mut members = lookup(class.field_names(), &*class);
+            if let Some(superclass) = class.superclass() {
+                members.push(("superclass".to_string(), Object::Class(superclass)));
+            }
+            let mut names = class.method_names();
+            names.sort();
+            for name in names {
+                if let Some(method) = class.find_method(&name) {
+                    let method = Rc::new(RefCell::new((*method).clone()));
+                    members.push((name, Object::Function(method)));
+                }
+            }
+            members
+        }
+        Object::List(list) => list
+            .borrow()
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (i.to_string(), x.clone()))
+            .collect(),
+        Object::Module(module) => {
+            let module = module.borrow();
+            lookup(module.names(), &*module)
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// The scopes around `environment`, innermost first, as sorted name and value pairs. The
+/// builtins at the end of the chain are left out.
+pub fn scopes(environment: &Rc<RefCell<Environment>>) -> Vec<Vec<(String, Object)>> {
+    let mut scopes = Vec::new();
+    let mut current = Some(environment.clone());
+    while let Some(environment) = current {
+        let environment = environment.borrow();
+        if environment.enclosing.is_none() {
+            break;
+        }
+        let mut names = environment.names();
+        names.sort();
+        scopes.push(
+            names
+                .into_iter()
+                .filter_map(|x| environment.get_own(&x).map(|value| (x, value)))
+                .collect(),
+        );
+        current = environment.enclosing.clone();
+    }
+    scopes
+}
+
+/// Look up `a.b.c` from the current scope.
+pub fn lookup(environment: &Rc<RefCell<Environment>>, path: &str) -> Result<Object, String> {
+    let mut names = path.split('.');
+    let first = names.next().unwrap_or_default();
+    let mut value = environment.borrow().get(first).map_err(error_message)?;
+    for name in names {
+        value = match value {
+            Object::Instance(instance) => instance.borrow().get(name),
+            Object::Class(class) => class.borrow().get(name),
+            Object::Module(module) => module.borrow().get(name),
+            value => return Err(format!("`{}` has no fields.", value)),
+        }
+        .map_err(error_message)?;
+    }
+    Ok(value)
+}
+
+fn error_message(err: InterpretError) -> String {
+    match err {
+        InterpretError::Error(error) => error.message,
+        _ => "Unreachable error!".to_string(),
+    }
+}
+
+/// A command-line debugger, reading commands from `input` whenever the script stops.
+pub struct Debugger<R: BufRead, W: Write> {
+    tracker: Tracker,
+    lines: Vec<String>,
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Debugger<R, W> {
+    pub fn new(source: &str, input: R, output: W) -> Debugger<R, W> {
+        Debugger {
+            tracker: Tracker::new(),
+            lines: source.lines().map(|x| x.to_string()).collect(),
+            input,
+            output,
+        }
+    }
+
+    pub fn output(&self) -> &W {
+        &self.output
+    }
+
+    fn source_line(&self, line: usize) -> &str {
+        self.lines
+            .get(line.wrapping_sub(1))
+            .map_or("", |x| x.trim())
+    }
+
+    fn show_value(&mut self, name: &str, value: &Object) {
+        let _ = writeln!(self.output, "{} = {}", name, describe(value));
+        for (field, value) in members(value) {
+            let _ = writeln!(self.output, "  {} = {}", field, describe(&value));
+        }
+    }
+
+    fn list(&mut self, line: usize) {
+        let start = line.saturating_sub(3).max(1);
+        let end = (line + 3).min(self.lines.len());
+        for number in start..=end {
+            let marker = if number == line { "->" } else { "  " };
+            let text = self.lines[number - 1].clone();
+            let _ = writeln!(self.output, "{} {:>4} {}", marker, number, text);
+        }
+    }
+
+    /// Read commands until one resumes the script.
+    fn prompt(&mut self, interpreter: &Interpreter, line: usize) -> Result<(), InterpretError> {
+        let name = self
+            .tracker
+            .frames
+            .last()
+            .map_or("", |x| &x.name)
+            .to_string();
+        let text = self.source_line(line).to_string();
+        let _ = writeln!(self.output, "[line {}] in {}: {}", line, name, text);
+
+        loop {
+            let _ = write!(self.output, "(debug) ");
+            let _ = self.output.flush();
+
+            let mut command = String::new();
+            // Without more commands, let the script run to its end.
+            if self.input.read_line(&mut command).unwrap_or(0) == 0 {
+                self.tracker.breakpoints.clear();
+                self.tracker.resume(Step::Continue);
+                return Ok(());
+            }
+
+            let mut words = command.split_whitespace();
+            let command = words.next().unwrap_or("");
+            let argument = words.next();
+            let number = argument.and_then(|x| x.parse::<usize>().ok());
+
+            match (command, number) {
+                ("c" | "continue", _) => return self.resume(Step::Continue),
+                ("s" | "step", _) => return self.resume(Step::Into),
+                ("n" | "next", _) => return self.resume(Step::Over(0)),
+                ("o" | "out", _) => return self.resume(Step::Out(0)),
+                ("q" | "quit", _) => {
+                    return Err(InterpretError::Abort(
+                        "Stopped by the debugger.".to_string(),
+                    ))
+                }
+                ("b" | "break", Some(line)) => {
+                    self.tracker.breakpoints.insert(line);
+                    let _ = writeln!(self.output, "Breakpoint at line {}.", line);
+                }
+                ("b" | "break", None) => {
+                    let lines: Vec<String> = self
+                        .tracker
+                        .breakpoints
+                        .iter()
+                        .map(|x| x.to_string())
+                        .collect();
+                    let _ = writeln!(self.output, "Breakpoints: {}", lines.join(", "));
+                }
+                ("d" | "delete", Some(line)) => {
+                    self.tracker.breakpoints.remove(&line);
+                }
+                ("bt" | "backtrace", _) => {
+                    let frames: Vec<Frame> = self.tracker.frames.iter().rev().cloned().collect();
+                    for (i, frame) in frames.iter().enumerate() {
+                        let _ =
+                            writeln!(self.output, "#{} {} [line {}]", i, frame.name, frame.line);
+                    }
+                }
+                ("env", _) => {
+                    let environment = interpreter.environment();
+                    let scopes = scopes(&environment);
+                    for (i, scope) in scopes.iter().enumerate() {
+                        let label = if i + 1 == scopes.len() {
+                            " (globals)"
+                        } else {
+                            ""
+                        };
+                        let values: Vec<String> = scope
+                            .iter()
+                            .map(|(name, value)| format!("{} = {}", name, describe(value)))
+                            .collect();
+                        let _ = writeln!(self.output, "#{}{} {}", i, label, values.join(", "));
+                    }
+                }
+                ("p" | "print", _) if argument.is_some() => {
+                    let path = argument.unwrap_or_default();
+                    match lookup(&interpreter.environment(), path) {
+                        Ok(value) => self.show_value(path, &value),
+                        Err(message) => {
+                            let _ = writeln!(self.output, "{}", message);
+                        }
+                    }
+                }
+                ("l" | "list", _) => self.list(line),
+                ("h" | "help", _) => {
+                    let _ = writeln!(self.output, "{}", HELP);
+                }
+                ("", _) => {}
+                _ => {
+                    let _ = writeln!(self.output, "Unknown command `{}`, try `help`.", command);
+                }
+            }
+        }
+    }
+
+    fn resume(&mut self, step: Step) -> Result<(), InterpretError> {
+        self.tracker.resume(step);
+        Ok(())
+    }
+}
+
+impl<R: BufRead, W: Write> Hook for Debugger<R, W> {
+    fn statement(&mut self, interpreter: &Interpreter, line: usize) -> Result<(), InterpretError> {
+        if self.tracker.statement(interpreter, line) {
+            self.prompt(interpreter, line)?;
+        }
+        Ok(())
+    }
+
+    fn enter(&mut self, interpreter: &Interpreter, name: &Rc<Token>) {
+        self.tracker.enter(interpreter, name);
+    }
+
+    fn exit(&mut self, _: &Interpreter, _: bool) {
+        self.tracker.exit();
+    }
+}
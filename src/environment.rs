@@ -1,7 +1,7 @@
 use crate::{interpreter::InterpretError, object::Object};
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     rc::{Rc, Weak},
 };
 
@@ -15,6 +15,7 @@ pub struct Environment {
     this: Weak<RefCell<Environment>>,
     pub enclosing: Option<Rc<RefCell<Environment>>>,
     values: HashMap<String, Object>,
+    constants: HashSet<String>,
 }
 
 impl Environment {
@@ -23,6 +24,7 @@ impl Environment {
             this: Weak::new(),
             enclosing,
             values: HashMap::new(),
+            constants: HashSet::new(),
         }));
 
         instance.borrow_mut().this = Rc::downgrade(&instance);
@@ -31,15 +33,30 @@ impl Environment {
     }
 
     pub fn define(&mut self, key: String, value: Object) {
+        self.constants.remove(&key);
         self.values.insert(key, value);
     }
 
+    /// Define a name that `set` refuses to change. Defining it again makes it a variable.
+    pub fn define_constant(&mut self, key: String, value: Object) {
+        self.values.insert(key.clone(), value);
+        self.constants.insert(key);
+    }
+
+    pub fn get_own(&self, key: &str) -> Option<Object> {
+        self.values.get(key).cloned()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.values.keys().cloned().collect()
+    }
+
     pub fn get_at(&self, distance: usize, key: &str) -> Result<Object, InterpretError> {
         if distance > 0 {
             if let Some(ref enclosing) = self.enclosing {
                 enclosing.borrow().get_at(distance - 1, key)
             } else {
-                Err(InterpretError::Error("Unreachable error!".to_string()))
+                Err(InterpretError::new("Unreachable error!"))
             }
         } else {
             self.get(key)
@@ -56,7 +73,7 @@ impl Environment {
             if let Some(ref enclosing) = self.enclosing {
                 enclosing.borrow_mut().set_at(distance - 1, key, value)
             } else {
-                Err(InterpretError::Error("Unreachable error!".to_string()))
+                Err(InterpretError::new("Unreachable error!"))
             }
         } else {
             self.set(key, value)
@@ -74,12 +91,18 @@ impl Stateful for Environment {
             return parent.borrow().get(key);
         }
 
-        Err(InterpretError::Error(format!(
+        Err(InterpretError::new(format!(
             "Undefined variable `{}`.",
             key,
         )))
     }
     fn set(&mut self, key: &str, value: Object) -> Result<(), InterpretError> {
+        if self.constants.contains(key) {
+            return Err(InterpretError::new(format!(
+                "Can't assign to constant `{}`.",
+                key
+            )));
+        }
         if self.values.contains_key(key) {
             self.values.insert(key.to_string(), value);
             return Ok(());
@@ -90,7 +113,7 @@ impl Stateful for Environment {
             return Ok(());
         }
 
-        Err(InterpretError::Error(format!(
+        Err(InterpretError::new(format!(
             "Undefined variable `{}`.",
             key,
         )))
@@ -40,12 +40,44 @@ pub enum Expr {
     Unary(UnaryExpr),
     Variable(VariableExpr),
     Assign(AssignExpr),
+    AssignList(AssignListExpr),
     Logical(LogicalExpr),
     Call(CallExpr),
     Get(GetExpr),
     Set(SetExpr),
     This(ThisExpr),
     Super(SuperExpr),
+    List(ListExpr),
+    Index(IndexExpr),
+    SetIndex(SetIndexExpr),
+    Update(UpdateExpr),
+    Conditional(ConditionalExpr),
+}
+
+impl Expr {
+    /// The line the expression starts on, unless it is made of literals only.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Expr::Binary(v) => v.left.expr.line(),
+            Expr::Grouping(v) => v.expression.expr.line(),
+            Expr::Literal(_) => None,
+            Expr::Unary(v) => Some(v.op.line),
+            Expr::Variable(v) => Some(v.name.line),
+            Expr::Assign(v) => Some(v.name.line),
+            Expr::AssignList(v) => Some(v.bracket.line),
+            Expr::Logical(v) => v.left.expr.line(),
+            Expr::Call(v) => v.callee.expr.line(),
+            Expr::Get(v) => v.object.expr.line(),
+            Expr::Set(v) => v.object.expr.line(),
+            Expr::This(v) => Some(v.keyword.line),
+            Expr::Super(v) => Some(v.keyword.line),
+            Expr::List(v) => Some(v.bracket.line),
+            Expr::Index(v) => v.object.expr.line(),
+            Expr::SetIndex(v) => v.object.expr.line(),
+            Expr::Update(v) => v.target.expr.line().or(Some(v.op.line)),
+            Expr::Conditional(v) => v.condition.expr.line(),
+        }
+    }
 }
 
 impl fmt::Display for Expr {
@@ -55,7 +87,20 @@ impl fmt::Display for Expr {
             Expr::Grouping(v) => v.fmt(fmt),
             Expr::Literal(v) => v.fmt(fmt),
             Expr::Unary(v) => v.fmt(fmt),
-            _ => Ok(()),
+            Expr::Variable(v) => v.fmt(fmt),
+            Expr::Assign(v) => v.fmt(fmt),
+            Expr::AssignList(v) => v.fmt(fmt),
+            Expr::Logical(v) => v.fmt(fmt),
+            Expr::Call(v) => v.fmt(fmt),
+            Expr::Get(v) => v.fmt(fmt),
+            Expr::Set(v) => v.fmt(fmt),
+            Expr::This(v) => v.fmt(fmt),
+            Expr::Super(v) => v.fmt(fmt),
+            Expr::List(v) => v.fmt(fmt),
+            Expr::Index(v) => v.fmt(fmt),
+            Expr::SetIndex(v) => v.fmt(fmt),
+            Expr::Update(v) => v.fmt(fmt),
+            Expr::Conditional(v) => v.fmt(fmt),
         }
     }
 }
@@ -109,7 +154,10 @@ impl LiteralExpr {
 
 impl fmt::Display for LiteralExpr {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(fmt, "{}", self.value)
+        match self.value {
+            Object::String(ref s) => write!(fmt, "{:?}", s),
+            ref value => write!(fmt, "{}", value),
+        }
     }
 }
 
@@ -142,6 +190,12 @@ impl VariableExpr {
     }
 }
 
+impl fmt::Display for VariableExpr {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{}", self.name.lexeme)
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct AssignExpr {
     pub name: Rc<Token>,
@@ -154,6 +208,48 @@ impl AssignExpr {
     }
 }
 
+impl fmt::Display for AssignExpr {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "(= {} {})", self.name.lexeme, self.value)
+    }
+}
+
+/// `[a, b] = [b, a]`, assigning the elements of a list to the targets in order.
+#[derive(Debug, PartialEq, Clone)]
+pub struct AssignListExpr {
+    pub bracket: Rc<Token>,
+    /// Variables, properties and list elements.
+    pub targets: Vec<Rc<HashExpr>>,
+    pub value: Rc<HashExpr>,
+}
+
+impl AssignListExpr {
+    pub fn new(
+        bracket: Rc<Token>,
+        targets: Vec<Rc<HashExpr>>,
+        value: Rc<HashExpr>,
+    ) -> AssignListExpr {
+        AssignListExpr {
+            bracket,
+            targets,
+            value,
+        }
+    }
+}
+
+impl fmt::Display for AssignListExpr {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "(= [")?;
+        for (i, target) in self.targets.iter().enumerate() {
+            if i > 0 {
+                write!(fmt, " ")?;
+            }
+            write!(fmt, "{}", target)?;
+        }
+        write!(fmt, "] {})", self.value)
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct LogicalExpr {
     pub left: Rc<HashExpr>,
@@ -167,6 +263,12 @@ impl LogicalExpr {
     }
 }
 
+impl fmt::Display for LogicalExpr {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "({} {} {})", self.op.lexeme, self.left, self.right)
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct CallExpr {
     pub callee: Rc<HashExpr>,
@@ -184,15 +286,38 @@ impl CallExpr {
     }
 }
 
+impl fmt::Display for CallExpr {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "(call {}", self.callee)?;
+        for argument in &self.arguments {
+            write!(fmt, " {}", argument)?;
+        }
+        write!(fmt, ")")
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct GetExpr {
     pub object: Rc<HashExpr>,
     pub name: Rc<Token>,
+    /// `object?.name`, which is nil along with the rest of the chain when the object is nil.
+    pub optional: bool,
 }
 
 impl GetExpr {
-    pub fn new(object: Rc<HashExpr>, name: Rc<Token>) -> GetExpr {
-        GetExpr { object, name }
+    pub fn new(object: Rc<HashExpr>, name: Rc<Token>, optional: bool) -> GetExpr {
+        GetExpr {
+            object,
+            name,
+            optional,
+        }
+    }
+}
+
+impl fmt::Display for GetExpr {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = if self.optional { "?." } else { "." };
+        write!(fmt, "({} {} {})", op, self.object, self.name.lexeme)
     }
 }
 
@@ -213,6 +338,16 @@ impl SetExpr {
     }
 }
 
+impl fmt::Display for SetExpr {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            fmt,
+            "(= (. {} {}) {})",
+            self.object, self.name.lexeme, self.value
+        )
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct ThisExpr {
     pub keyword: Rc<Token>,
@@ -224,6 +359,12 @@ impl ThisExpr {
     }
 }
 
+impl fmt::Display for ThisExpr {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "this")
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct SuperExpr {
     pub keyword: Rc<Token>,
@@ -235,3 +376,155 @@ impl SuperExpr {
         SuperExpr { keyword, method }
     }
 }
+
+impl fmt::Display for SuperExpr {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "(super {})", self.method.lexeme)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ListExpr {
+    pub bracket: Rc<Token>,
+    pub elements: Vec<Rc<HashExpr>>,
+}
+
+impl ListExpr {
+    pub fn new(bracket: Rc<Token>, elements: Vec<Rc<HashExpr>>) -> ListExpr {
+        ListExpr { bracket, elements }
+    }
+}
+
+impl fmt::Display for ListExpr {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "(list")?;
+        for element in &self.elements {
+            write!(fmt, " {}", element)?;
+        }
+        write!(fmt, ")")
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct IndexExpr {
+    pub object: Rc<HashExpr>,
+    pub bracket: Rc<Token>,
+    pub index: Rc<HashExpr>,
+}
+
+impl IndexExpr {
+    pub fn new(object: Rc<HashExpr>, bracket: Rc<Token>, index: Rc<HashExpr>) -> IndexExpr {
+        IndexExpr {
+            object,
+            bracket,
+            index,
+        }
+    }
+}
+
+impl fmt::Display for IndexExpr {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "([] {} {})", self.object, self.index)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SetIndexExpr {
+    pub object: Rc<HashExpr>,
+    pub bracket: Rc<Token>,
+    pub index: Rc<HashExpr>,
+    pub value: Rc<HashExpr>,
+}
+
+impl SetIndexExpr {
+    pub fn new(
+        object: Rc<HashExpr>,
+        bracket: Rc<Token>,
+        index: Rc<HashExpr>,
+        value: Rc<HashExpr>,
+    ) -> SetIndexExpr {
+        SetIndexExpr {
+            object,
+            bracket,
+            index,
+            value,
+        }
+    }
+}
+
+impl fmt::Display for SetIndexExpr {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            fmt,
+            "(= ([] {} {}) {})",
+            self.object, self.index, self.value
+        )
+    }
+}
+
+/// `a += b` and the other compound assignments, or `++a`, `a--` and the like when there is no
+/// value. The target is a `Variable`, `Get` or `Index` whose parts are evaluated once.
+#[derive(Debug, PartialEq, Clone)]
+pub struct UpdateExpr {
+    pub target: Rc<HashExpr>,
+    pub op: Rc<Token>,
+    pub value: Option<Rc<HashExpr>>,
+    pub prefix: bool,
+}
+
+impl UpdateExpr {
+    pub fn new(
+        target: Rc<HashExpr>,
+        op: Rc<Token>,
+        value: Option<Rc<HashExpr>>,
+        prefix: bool,
+    ) -> UpdateExpr {
+        UpdateExpr {
+            target,
+            op,
+            value,
+            prefix,
+        }
+    }
+}
+
+impl fmt::Display for UpdateExpr {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value {
+            Some(ref value) => write!(fmt, "({} {} {})", self.op.lexeme, self.target, value),
+            None if self.prefix => write!(fmt, "({} {})", self.op.lexeme, self.target),
+            None => write!(fmt, "({} {})", self.target, self.op.lexeme),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ConditionalExpr {
+    pub condition: Rc<HashExpr>,
+    pub then_branch: Rc<HashExpr>,
+    pub else_branch: Rc<HashExpr>,
+}
+
+impl ConditionalExpr {
+    pub fn new(
+        condition: Rc<HashExpr>,
+        then_branch: Rc<HashExpr>,
+        else_branch: Rc<HashExpr>,
+    ) -> ConditionalExpr {
+        ConditionalExpr {
+            condition,
+            then_branch,
+            else_branch,
+        }
+    }
+}
+
+impl fmt::Display for ConditionalExpr {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            fmt,
+            "(?: {} {} {})",
+            self.condition, self.then_branch, self.else_branch
+        )
+    }
+}
@@ -0,0 +1,283 @@
+use crate::{
+    parser::parse,
+    scanner::{scan_tokens, scan_tokens_with_comments},
+    token::{Token, TokenType},
+};
+use std::rc::Rc;
+
+const INDENT: &str = "    ";
+
+/// Re-emit `code` in the canonical style: four-space indentation, one statement per line,
+/// opening braces on the same line and single spaces around binary operators. Comments and
+/// single blank lines between statements are kept. Code that doesn't parse is an error.
+pub fn format(code: &str) -> Result<String, String> {
+    parse(scan_tokens(code)?)?;
+
+    let tokens = scan_tokens_with_comments(code)?;
+    let mut formatter = Formatter::new(&tokens);
+    formatter.run();
+
+    Ok(formatter.out)
+}
+
+#[derive(PartialEq)]
+enum Brace {
+    Block,
+    // `import { a, b } from ...`
+    Inline,
+}
+
+struct Formatter<'a> {
+    tokens: &'a [Rc<Token>],
+    out: String,
+    depth: usize,
+    parens: usize,
+    braces: Vec<Brace>,
+    at_line_start: bool,
+    // The last token written that isn't a comment, and whether it was a unary operator.
+    previous: Option<&'a Token>,
+    previous_unary: bool,
+    // Source line of the last token written, comments included.
+    last_line: usize,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(tokens: &'a [Rc<Token>]) -> Formatter<'a> {
+        Formatter {
+            tokens,
+            out: String::new(),
+            depth: 0,
+            parens: 0,
+            braces: Vec::new(),
+            at_line_start: true,
+            previous: None,
+            previous_unary: false,
+            last_line: 0,
+        }
+    }
+
+    fn run(&mut self) {
+        let mut i = 0;
+        while let Some(token) = self.tokens.get(i) {
+            let next = self.tokens.get(i + 1).map(|x| x.token_type.clone());
+            match token.token_type {
+                TokenType::Eof => break,
+                TokenType::Comment => self.comment(token),
+                TokenType::LeftBrace if next == Some(TokenType::RightBrace) => {
+                    // An empty block stays on one line: `{}`.
+                    self.write(token);
+                    self.out.push('}');
+                    self.last_line = self.tokens[i + 1].line;
+                    i += 1;
+                    self.after_right_brace(i);
+                }
+                TokenType::LeftBrace => {
+                    // `import {a, b} from ...` and `var {x, y} = ...` list names, not statements.
+                    let inline = self.previous.is_some_and(|x| {
+                        matches!(
+                            x.token_type,
+                            TokenType::Import | TokenType::Var | TokenType::Let | TokenType::Const
+                        )
+                    });
+                    self.write(token);
+                    if inline {
+                        self.braces.push(Brace::Inline);
+                    } else {
+                        self.braces.push(Brace::Block);
+                        self.depth += 1;
+                        self.at_line_start = true;
+                    }
+                }
+                TokenType::RightBrace => {
+                    if self.braces.pop() != Some(Brace::Inline) {
+                        self.depth = self.depth.saturating_sub(1);
+                        self.at_line_start = true;
+                        self.write(token);
+                        self.after_right_brace(i);
+                    } else {
+                        self.write(token);
+                    }
+                }
+                TokenType::LeftParen => {
+                    self.write(token);
+                    self.parens += 1;
+                }
+                TokenType::RightParen => {
+                    self.write(token);
+                    self.parens = self.parens.saturating_sub(1);
+                }
+                TokenType::Semicolon => {
+                    self.write(token);
+                    // Semicolons inside `for (...)` don't end a line.
+                    if self.parens == 0 {
+                        self.at_line_start = true;
+                    }
+                }
+                _ => self.write(token),
+            }
+            i += 1;
+        }
+
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+    }
+
+    fn after_right_brace(&mut self, i: usize) {
+        let next = self.tokens.get(i + 1).map(|x| &x.token_type);
+        self.at_line_start = !matches!(
+            next,
+            Some(TokenType::Else)
+                | Some(TokenType::Catch)
+                | Some(TokenType::Finally)
+                | Some(TokenType::Semicolon)
+                | Some(TokenType::Comma)
+                | Some(TokenType::RightParen)
+        );
+    }
+
+    fn comment(&mut self, token: &Token) {
+        let text = token.lexeme.trim_end();
+
+        // A comment after code on the same line stays there.
+        if !self.out.is_empty() && token.line == self.last_line {
+            self.out.push(' ');
+            self.out.push_str(text);
+        } else {
+            self.at_line_start = true;
+            self.start_line(token);
+            self.out.push_str(text);
+        }
+
+        self.at_line_start = true;
+        self.last_line = token.line;
+    }
+
+    fn start_line(&mut self, token: &Token) {
+        if !self.out.is_empty() {
+            self.out.push('\n');
+            // Keep one blank line where the source had any, except right after `{`
+            // or before `}`.
+            let after_brace = self.out.ends_with("{\n");
+            if token.line > self.last_line + 1
+                && !after_brace
+                && token.token_type != TokenType::RightBrace
+            {
+                self.out.push('\n');
+            }
+        }
+        self.out.push_str(&INDENT.repeat(self.depth));
+        self.at_line_start = false;
+    }
+
+    fn write(&mut self, token: &'a Token) {
+        if self.at_line_start {
+            self.start_line(token);
+        } else if self.space_before(token) {
+            self.out.push(' ');
+        }
+
+        self.out.push_str(&token.lexeme);
+        self.previous_unary = self.is_unary(token);
+        self.previous = Some(token);
+        self.last_line = token.line;
+    }
+
+    fn is_unary(&self, token: &Token) -> bool {
+        match token.token_type {
+            TokenType::Bang | TokenType::Tilde => true,
+            TokenType::Minus | TokenType::PlusPlus | TokenType::MinusMinus => !self.after_operand(),
+            _ => false,
+        }
+    }
+
+    /// Whether the previous token ends an operand, `a++` included.
+    fn after_operand(&self) -> bool {
+        match self.previous {
+            Some(token)
+                if matches!(
+                    token.token_type,
+                    TokenType::PlusPlus | TokenType::MinusMinus
+                ) =>
+            {
+                !self.previous_unary
+            }
+            Some(token) => Formatter::ends_operand(token),
+            None => false,
+        }
+    }
+
+    /// Whether `token` can be the last token of an operand, making a following `-` binary.
+    fn ends_operand(token: &Token) -> bool {
+        matches!(
+            token.token_type,
+            TokenType::Identifier
+                | TokenType::Number
+                | TokenType::String
+                | TokenType::RightParen
+                | TokenType::RightBracket
+                | TokenType::True
+                | TokenType::False
+                | TokenType::Nil
+                | TokenType::This
+        )
+    }
+
+    /// Whether `token` written right after `previous` would lex as another token, as
+    /// `- -x` would as `--x`.
+    fn would_merge(previous: &Token, token: &Token) -> bool {
+        matches!(
+            (previous.lexeme.chars().last(), token.lexeme.chars().next()),
+            (Some('-'), Some('-')) | (Some('+'), Some('+'))
+        )
+    }
+
+    fn space_before(&self, token: &Token) -> bool {
+        let Some(previous) = self.previous else {
+            return false;
+        };
+
+        if self.previous_unary {
+            return Formatter::would_merge(previous, token);
+        }
+
+        // `a++`
+        if matches!(
+            token.token_type,
+            TokenType::PlusPlus | TokenType::MinusMinus
+        ) && self.after_operand()
+        {
+            return false;
+        }
+
+        match previous.token_type {
+            TokenType::LeftParen
+            | TokenType::LeftBracket
+            | TokenType::Dot
+            | TokenType::QuestionDot
+            | TokenType::Interpolation => return false,
+            _ => {}
+        }
+
+        // The rest of a string after `${...}` hugs the interpolated expression.
+        let string = matches!(
+            token.token_type,
+            TokenType::String | TokenType::Interpolation
+        );
+        if string && token.lexeme.starts_with('}') {
+            return false;
+        }
+
+        match token.token_type {
+            TokenType::RightParen
+            | TokenType::RightBracket
+            | TokenType::Semicolon
+            | TokenType::Comma
+            | TokenType::Dot
+            | TokenType::QuestionDot => false,
+            // Calls and subscripts hug their callee, `if (` and `= [` don't.
+            TokenType::LeftParen | TokenType::LeftBracket => !Formatter::ends_operand(previous),
+            _ => true,
+        }
+    }
+}
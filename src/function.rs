@@ -21,7 +21,7 @@ pub trait IsFunction: fmt::Debug + fmt::Display {
         &self,
         _: Rc<RefCell<dyn IsInstance>>,
     ) -> Result<Rc<RefCell<dyn IsFunction>>, InterpretError> {
-        Err(InterpretError::Error("Unreachable error!".to_string()))
+        Err(InterpretError::new("Unreachable error!"))
     }
 }
 
@@ -50,10 +50,79 @@ impl fmt::Display for Clock {
     }
 }
 
+pub type NativeFn = fn(&mut Interpreter, Vec<Object>) -> Result<Object, InterpretError>;
+
+#[derive(Debug)]
+pub struct Native {
+    name: &'static str,
+    arity: usize,
+    fun: NativeFn,
+}
+
+impl Native {
+    pub fn new(name: &'static str, arity: usize, fun: NativeFn) -> Native {
+        Native { name, arity, fun }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl IsFunction for Native {
+    fn arity(&self) -> usize {
+        self.arity
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Object>,
+    ) -> Result<Object, InterpretError> {
+        (self.fun)(interpreter, arguments)
+    }
+}
+
+impl fmt::Display for Native {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "<builtin-fun {}>", self.name)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Str {}
+
+impl Str {
+    pub fn new() -> Str {
+        Str {}
+    }
+}
+
+impl IsFunction for Str {
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Object>,
+    ) -> Result<Object, InterpretError> {
+        Ok(Object::String(interpreter.stringify(&arguments[0])?))
+    }
+}
+
+impl fmt::Display for Str {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "<builtin-fun str>")
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Function {
     declaration: Rc<FunctionStmt>,
     closure: Rc<RefCell<Environment>>,
+    globals: Rc<RefCell<Environment>>,
     is_initializer: bool,
 }
 
@@ -61,11 +130,13 @@ impl Function {
     pub fn new(
         declaration: Rc<FunctionStmt>,
         closure: Rc<RefCell<Environment>>,
+        globals: Rc<RefCell<Environment>>,
         is_initializer: bool,
     ) -> Function {
         Function {
             declaration,
             closure,
+            globals,
             is_initializer,
         }
     }
@@ -90,7 +161,29 @@ impl IsFunction for Function {
             )
         }
 
-        let value = interpreter.execute_block(&self.declaration.body, environment)?;
+        // Globals are looked up in the module the function was declared in.
+        let globals = interpreter.swap_globals(self.globals.clone());
+        let hook = interpreter.hook();
+        if let Some(ref hook) = hook {
+            hook.borrow_mut().enter(interpreter, &self.declaration.name);
+        }
+        let result = interpreter.execute_block(&self.declaration.body, environment);
+        if let Some(ref hook) = hook {
+            let failed = matches!(
+                result,
+                Err(InterpretError::Error(_))
+                    | Err(InterpretError::Throw(..))
+                    | Err(InterpretError::Abort(_))
+            );
+            hook.borrow_mut().exit(interpreter, failed);
+        }
+        interpreter.swap_globals(globals);
+
+        let value = match result {
+            Ok(()) => Object::Nil,
+            Err(InterpretError::Return(value)) => value,
+            Err(err) => return Err(err),
+        };
 
         if self.is_initializer {
             return self.closure.borrow().get("this");
@@ -110,6 +203,7 @@ impl IsFunction for Function {
         Ok(Rc::new(RefCell::new(Function::new(
             self.declaration.clone(),
             environment,
+            self.globals.clone(),
             self.is_initializer,
         ))))
     }
@@ -12,7 +12,13 @@ use std::{
     rc::{Rc, Weak},
 };
 
-pub trait IsInstance: fmt::Debug + fmt::Display + Stateful {}
+pub trait IsInstance: fmt::Debug + fmt::Display + Stateful {
+    fn class(&self) -> Option<Rc<RefCell<Class>>> {
+        None
+    }
+
+    fn field_names(&self) -> Vec<String>;
+}
 
 #[derive(Debug)]
 pub struct Instance {
@@ -50,7 +56,7 @@ impl Stateful for Instance {
             return Ok(Object::Function(function));
         }
 
-        Err(InterpretError::Error(format!(
+        Err(InterpretError::new(format!(
             "Undefined property `{}`.",
             key,
         )))
@@ -68,4 +74,12 @@ impl fmt::Display for Instance {
     }
 }
 
-impl IsInstance for Instance {}
+impl IsInstance for Instance {
+    fn class(&self) -> Option<Rc<RefCell<Class>>> {
+        Some(self.class.clone())
+    }
+
+    fn field_names(&self) -> Vec<String> {
+        self.fields.keys().cloned().collect()
+    }
+}
@@ -1,36 +1,118 @@
 use crate::{
     class::{Class, IsClass},
     environment::{Environment, Stateful},
-    expr::{Expr, HashExpr},
-    function::{Clock, Function, IsFunction},
+    expr::{CallExpr, Expr, HashExpr, UpdateExpr},
+    function::{Clock, Function, IsFunction, Str},
+    instance::IsInstance,
+    jlox,
+    module::Module,
     object::Object,
-    resolver::Resolver,
-    stmt::Stmt,
+    parser::parse,
+    reflect,
+    resolver::{ResolveError, Resolver},
+    scanner::scan_tokens,
+    stdlib,
+    stmt::{CatchClause, FunctionStmt, ImportStmt, Stmt},
+    testing,
     token::{Token, TokenType},
+    traits::Trait,
 };
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::HashMap,
+    fmt, fs,
+    io::Write,
+    mem,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+const PRELUDE: &str = include_str!("prelude.lox");
+
+/// Bundled modules are cached under this pseudo directory.
+const STDLIB: &str = "<stdlib>";
 
-pub fn interpret(statements: &Vec<Rc<Stmt>>) {
-    let mut resolver = Resolver::new();
-    let locals = resolver.resolve(statements);
+pub fn interpret(statements: &[Rc<Stmt>], path: Option<&Path>) {
+    let locals = match Resolver::new().resolve(statements) {
+        Ok(locals) => locals,
+        Err(err) => panic!("{}", err),
+    };
 
     // println!("{:?}", locals);
 
     let mut interpreter = Interpreter::new(locals);
+    if let Err(err) = interpreter.run(statements, path) {
+        panic!("{}", err);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RuntimeError {
+    pub line: Option<usize>,
+    pub location: Option<String>,
+    pub message: String,
+}
 
-    for statement in statements {
-        if let Err(err) = interpreter.execute(statement) {
-            match err {
-                InterpretError::Error(message) => panic!("{}", message),
-                _ => panic!("Unreachable error!"),
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, &self.location) {
+            (Some(line), Some(location)) => {
+                write!(fmt, "[line {}] <{}> : {}", line, location, self.message)
             }
+            (Some(line), None) => write!(fmt, "[line {}] : {}", line, self.message),
+            _ => write!(fmt, "{}", self.message),
         }
     }
 }
 
 pub enum InterpretError {
-    Error(String),
+    Error(RuntimeError),
     Return(Object),
+    Throw(Object, usize),
+    /// Stops the program, passing through `catch` and `finally`, e.g. when a debugger quits.
+    Abort(String),
+}
+
+impl InterpretError {
+    pub fn new(message: impl Into<String>) -> InterpretError {
+        InterpretError::Error(RuntimeError {
+            line: None,
+            location: None,
+            message: message.into(),
+        })
+    }
+
+    pub fn at(token: &Token, message: impl Into<String>) -> InterpretError {
+        InterpretError::Error(RuntimeError {
+            line: Some(token.line),
+            location: Some(format!("{:?}", token)),
+            message: message.into(),
+        })
+    }
+
+    /// Remember where an error without a location surfaced, so `catch` can report it.
+    fn or_line(self, line: usize) -> InterpretError {
+        match self {
+            InterpretError::Error(mut error) => {
+                error.line = error.line.or(Some(line));
+                InterpretError::Error(error)
+            }
+            err => err,
+        }
+    }
+
+    /// Place an error raised without a location, as natives raise them, at `token`.
+    fn or_at(self, token: &Token) -> InterpretError {
+        match self {
+            InterpretError::Error(RuntimeError {
+                line: None,
+                message,
+                ..
+            }) => InterpretError::at(token, message),
+            err => err,
+        }
+    }
 }
 
 pub trait Visitor<T, U> {
@@ -38,27 +120,491 @@ pub trait Visitor<T, U> {
     fn visit_stmt(&mut self, stmt: &Stmt) -> Result<(), U>;
 }
 
+/// Watches a program run, for debuggers and profilers. With no hook set, running a
+/// statement only pays for checking that.
+pub trait Hook {
+    /// Called before a statement starting on `line` runs. An error stops the program.
+    fn statement(&mut self, interpreter: &Interpreter, line: usize) -> Result<(), InterpretError>;
+
+    /// Called when a Lox function named `name` is entered, before its body runs.
+    fn enter(&mut self, _interpreter: &Interpreter, _name: &Rc<Token>) {}
+
+    /// Called when the function entered last is left, by returning or, when `failed`, by an
+    /// error or exception unwinding it.
+    fn exit(&mut self, _interpreter: &Interpreter, _failed: bool) {}
+}
+
 pub struct Interpreter {
     locals: Rc<RefCell<HashMap<HashExpr, usize>>>,
+    builtins: Rc<RefCell<Environment>>,
     globals: Rc<RefCell<Environment>>,
+    /// The globals of the script being run, as opposed to those of the modules it imports.
+    script: Rc<RefCell<Environment>>,
     environment: Rc<RefCell<Environment>>,
+    stringifying: Vec<Rc<RefCell<dyn IsInstance>>>,
+    stringifying_lists: Vec<Rc<RefCell<Vec<Object>>>>,
+    error_class: Option<Rc<RefCell<dyn IsClass>>>,
+    modules: HashMap<PathBuf, Rc<RefCell<Module>>>,
+    loading: Vec<PathBuf>,
+    search_paths: Vec<PathBuf>,
+    stdlib_natives: Option<Rc<RefCell<Environment>>>,
+    resolving: bool,
+    hook: Option<Rc<RefCell<dyn Hook>>>,
+    output: Option<Rc<RefCell<dyn Write>>>,
+    test: Option<String>,
+    strict: bool,
 }
 
 impl Interpreter {
-    fn new(locals: Rc<RefCell<HashMap<HashExpr, usize>>>) -> Interpreter {
-        let globals = Environment::new(None);
+    pub fn new(locals: Rc<RefCell<HashMap<HashExpr, usize>>>) -> Interpreter {
+        let builtins = Environment::new(None);
 
-        globals.borrow_mut().define(
+        builtins.borrow_mut().define(
             "clock".to_string(),
             Object::Function(Rc::new(RefCell::new(Clock::new()))),
         );
+        builtins.borrow_mut().define(
+            "str".to_string(),
+            Object::Function(Rc::new(RefCell::new(Str::new()))),
+        );
+        for native in reflect::natives().into_iter().chain(testing::natives()) {
+            builtins.borrow_mut().define(
+                native.name().to_string(),
+                Object::Function(Rc::new(RefCell::new(native))),
+            );
+        }
 
-        let environment = globals.clone();
-
-        Interpreter {
+        let mut interpreter = Interpreter {
             locals,
+            builtins: builtins.clone(),
+            globals: builtins.clone(),
+            script: builtins.clone(),
+            environment: builtins.clone(),
+            stringifying: Vec::new(),
+            stringifying_lists: Vec::new(),
+            error_class: None,
+            modules: HashMap::new(),
+            loading: Vec::new(),
+            search_paths: Vec::new(),
+            stdlib_natives: None,
+            resolving: true,
+            hook: None,
+            output: None,
+            test: None,
+            strict: false,
+        };
+
+        interpreter.run_prelude();
+
+        // Every module, the main script included, gets its own globals on top of the builtins.
+        interpreter.globals = Environment::new(Some(builtins));
+        interpreter.script = interpreter.globals.clone();
+        interpreter.environment = interpreter.globals.clone();
+
+        interpreter
+    }
+
+    /// Execute a program, reporting runtime errors and uncaught exceptions as messages.
+    pub fn run(&mut self, statements: &[Rc<Stmt>], path: Option<&Path>) -> Result<(), String> {
+        let path = path.and_then(|x| x.canonicalize().ok());
+        if let Some(ref path) = path {
+            self.loading.push(path.clone());
+        }
+
+        let mut result = Ok(());
+        for statement in statements {
+            if let Err(err) = self.execute(statement) {
+                result = Err(match err {
+                    InterpretError::Error(error) if self.strict => jlox::runtime_error(&error),
+                    InterpretError::Error(error) => error.to_string(),
+                    InterpretError::Throw(value, line) => {
+                        let text = self.stringify(&value).unwrap_or_else(|_| value.to_string());
+                        format!("[line {}] : Uncaught exception: {}", line, text)
+                    }
+                    InterpretError::Abort(message) => message,
+                    _ => "Unreachable error!".to_string(),
+                });
+                break;
+            }
+        }
+
+        if path.is_some() {
+            self.loading.pop();
+        }
+
+        result
+    }
+
+    /// Merge the scope distances of another resolved chunk of code, e.g. a REPL line.
+    pub fn add_locals(&mut self, locals: Rc<RefCell<HashMap<HashExpr, usize>>>) {
+        self.locals
+            .borrow_mut()
+            .extend(locals.borrow().iter().map(|(k, v)| (k.clone(), *v)));
+    }
+
+    pub fn set_search_paths(&mut self, search_paths: Vec<PathBuf>) {
+        self.search_paths = search_paths;
+    }
+
+    /// Look variables up by walking the environment chain instead of using the resolver.
+    pub fn disable_resolution(&mut self) {
+        self.resolving = false;
+    }
+
+    /// Names visible at the top level of a module, builtins included.
+    pub fn global_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        let mut environment = Some(self.globals.clone());
+        while let Some(current) = environment {
+            names.extend(current.borrow().names());
+            environment = current.borrow().enclosing.clone();
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Names defined before any user code runs: natives, the prelude and `args`.
+    pub fn builtin_names() -> Vec<String> {
+        let interpreter = Interpreter::new(Rc::new(RefCell::new(HashMap::new())));
+        let mut names = interpreter.global_names();
+        names.push("args".to_string());
+        names
+    }
+
+    pub fn set_hook(&mut self, hook: Rc<RefCell<dyn Hook>>) {
+        self.hook = Some(hook);
+    }
+
+    pub fn hook(&self) -> Option<Rc<RefCell<dyn Hook>>> {
+        self.hook.clone()
+    }
+
+    /// Send what the program prints to `output` instead of stdout.
+    pub fn set_output(&mut self, output: Rc<RefCell<dyn Write>>) {
+        self.output = Some(output);
+    }
+
+    /// Write program output, to stdout unless `set_output` says otherwise.
+    pub fn write(&self, text: &str) {
+        match self.output {
+            Some(ref output) => {
+                let mut output = output.borrow_mut();
+                let _ = output.write_all(text.as_bytes());
+                let _ = output.flush();
+            }
+            None => print!("{}", text),
+        }
+    }
+
+    /// Behave like jlox: only `nil` and `false` are falsy, numbers, functions, classes and
+    /// instances print as in Java, and errors are reported in its format.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn is_truthy(&self, value: &Object) -> bool {
+        match self.strict {
+            true => !matches!(value, Object::Nil | Object::Boolean(false)),
+            false => value.is_truthy(),
+        }
+    }
+
+    /// Run the body of the `test` block named `name`. Test blocks are skipped otherwise.
+    pub fn select_test(&mut self, name: &str) {
+        self.test = Some(name.to_string());
+    }
+
+    /// The innermost scope of the code running now.
+    pub fn environment(&self) -> Rc<RefCell<Environment>> {
+        self.environment.clone()
+    }
+
+    /// The globals of the module the code running now was declared in.
+    pub fn globals(&self) -> Rc<RefCell<Environment>> {
+        self.globals.clone()
+    }
+
+    /// Whether the code running now was declared in the script rather than an imported module.
+    pub fn in_script(&self) -> bool {
+        Rc::ptr_eq(&self.globals, &self.script)
+    }
+
+    pub fn define_global(&mut self, name: &str, value: Object) {
+        self.globals.borrow_mut().define(name.to_string(), value);
+    }
+
+    fn run_prelude(&mut self) {
+        let statements = parse(scan_tokens(PRELUDE).unwrap()).unwrap();
+        self.resolve(&statements).unwrap();
+
+        for statement in &statements {
+            if self.execute(statement).is_err() {
+                panic!("Unreachable error!");
+            }
+        }
+
+        if let Ok(Object::Class(class)) = self.builtins.borrow().get("Error") {
+            self.error_class = Some(class);
+        }
+    }
+
+    fn resolve(&mut self, statements: &[Rc<Stmt>]) -> Result<(), ResolveError> {
+        if self.resolving {
+            let locals = Resolver::new().resolve(statements)?;
+            self.add_locals(locals);
+        }
+        Ok(())
+    }
+
+    pub fn swap_globals(&mut self, globals: Rc<RefCell<Environment>>) -> Rc<RefCell<Environment>> {
+        mem::replace(&mut self.globals, globals)
+    }
+
+    fn import_module(&mut self, stmt: &ImportStmt) -> Result<Rc<RefCell<Module>>, InterpretError> {
+        let relative = match stmt.path.literal {
+            Object::String(ref s) => PathBuf::from(s),
+            _ => return Err(InterpretError::new("Unreachable error!")),
+        };
+
+        let (path, bundled) = match stdlib::source(&relative.to_string_lossy()) {
+            Some(code) => (Path::new(STDLIB).join(&relative), Some(code)),
+            None => (self.locate_module(&stmt.path, &relative)?, None),
+        };
+
+        if let Some(module) = self.modules.get(&path) {
+            return Ok(module.clone());
+        }
+
+        if let Some(start) = self.loading.iter().position(|x| *x == path) {
+            let cycle: Vec<String> = self.loading[start..]
+                .iter()
+                .chain([&path])
+                .map(|x| {
+                    x.file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string()
+                })
+                .collect();
+            return Err(InterpretError::at(
+                &stmt.path,
+                format!("Import cycle detected: {}.", cycle.join(" -> ")),
+            ));
+        }
+
+        let code = match bundled {
+            Some(code) => code.to_string(),
+            None => fs::read_to_string(&path).map_err(|err| {
+                InterpretError::at(
+                    &stmt.path,
+                    format!("Can't import `{}`: {}.", relative.display(), err),
+                )
+            })?,
+        };
+
+        let statements = scan_tokens(&code)
+            .and_then(parse)
+            .and_then(|x| self.resolve(&x).map(|_| x))
+            .map_err(InterpretError::new)?;
+
+        // Stdlib modules additionally see the primitives they are built on.
+        let enclosing = match bundled {
+            Some(_) => self.stdlib_natives(),
+            None => self.builtins.clone(),
+        };
+        let globals = Environment::new(Some(enclosing));
+
+        self.loading.push(path.clone());
+        let previous_globals = self.swap_globals(globals.clone());
+        let previous_environment = mem::replace(&mut self.environment, globals.clone());
+
+        let result = statements
+            .iter()
+            .try_for_each(|statement| self.execute(statement));
+
+        self.environment = previous_environment;
+        self.swap_globals(previous_globals);
+        self.loading.pop();
+
+        result?;
+
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        let module = Rc::new(RefCell::new(Module::new(
+            name.to_string(),
+            path.clone(),
             globals,
-            environment,
+        )));
+        self.modules.insert(path, module.clone());
+
+        Ok(module)
+    }
+
+    /// Find an imported file next to the importing one, or else in the search paths.
+    fn locate_module(&self, token: &Token, relative: &Path) -> Result<PathBuf, InterpretError> {
+        // Paths are relative to the importing file, or to the working directory for code
+        // that doesn't come from a file.
+        let base = self
+            .loading
+            .last()
+            .filter(|x| !x.starts_with(STDLIB))
+            .and_then(|x| x.parent())
+            .map(|x| x.to_path_buf())
+            .unwrap_or_default();
+
+        let mut candidates = vec![relative.to_path_buf()];
+        if relative.extension().is_none() {
+            candidates.push(relative.with_extension("lox"));
+        }
+
+        let bases = [base].into_iter().chain(self.search_paths.iter().cloned());
+        for base in bases {
+            for candidate in &candidates {
+                if let Ok(path) = base.join(candidate).canonicalize() {
+                    if path.is_file() {
+                        return Ok(path);
+                    }
+                }
+            }
+        }
+
+        Err(InterpretError::at(
+            token,
+            format!("Can't import `{}`: module not found.", relative.display()),
+        ))
+    }
+
+    fn stdlib_natives(&mut self) -> Rc<RefCell<Environment>> {
+        if let Some(ref environment) = self.stdlib_natives {
+            return environment.clone();
+        }
+
+        let environment = Environment::new(Some(self.builtins.clone()));
+        for native in stdlib::natives() {
+            environment.borrow_mut().define(
+                native.name().to_string(),
+                Object::Function(Rc::new(RefCell::new(native))),
+            );
+        }
+        self.stdlib_natives = Some(environment.clone());
+
+        environment
+    }
+
+    /// Wrap a runtime error into an `Error` instance a `catch` clause can bind.
+    fn error_object(&mut self, error: RuntimeError) -> Result<Object, InterpretError> {
+        let class = match self.error_class {
+            Some(ref class) => class.clone(),
+            None => return Err(InterpretError::Error(error)),
+        };
+
+        let object = class
+            .borrow()
+            .call(self, vec![Object::String(error.message)])?;
+        if let Object::Instance(ref instance) = object {
+            let line = error
+                .line
+                .map_or(Object::Nil, |x| Object::Integer(x as i64));
+            instance.borrow_mut().set("line", line)?;
+        }
+
+        Ok(object)
+    }
+
+    fn is_error_instance(&self, value: &Object) -> bool {
+        let (Object::Instance(instance), Some(target)) = (value, &self.error_class) else {
+            return false;
+        };
+
+        let mut current = instance
+            .borrow()
+            .class()
+            .map(|x| x as Rc<RefCell<dyn IsClass>>);
+        while let Some(class) = current {
+            if Rc::ptr_eq(&class, target) {
+                return true;
+            }
+            current = class.borrow().superclass();
+        }
+
+        false
+    }
+
+    fn execute_catch(
+        &mut self,
+        handler: &CatchClause,
+        value: Object,
+    ) -> Result<(), InterpretError> {
+        let environment = Environment::new(Some(self.environment.clone()));
+        environment
+            .borrow_mut()
+            .define(handler.name.lexeme.clone(), value);
+        self.execute_block(&handler.body, environment)
+    }
+
+    pub fn stringify(&mut self, value: &Object) -> Result<String, InterpretError> {
+        if self.strict {
+            return Ok(jlox::stringify(value));
+        }
+
+        let instance = match value {
+            Object::Instance(instance) => instance.clone(),
+            Object::List(list) => {
+                // A list that contains itself is printed as `[...]` the second time round.
+                if self.stringifying_lists.iter().any(|x| Rc::ptr_eq(x, list)) {
+                    return Ok("[...]".to_string());
+                }
+
+                self.stringifying_lists.push(list.clone());
+                let mut items: Vec<String> = Vec::new();
+                let mut result = Ok(());
+                for item in list.borrow().clone() {
+                    match self.stringify(&item) {
+                        Ok(item) => items.push(item),
+                        Err(err) => {
+                            result = Err(err);
+                            break;
+                        }
+                    }
+                }
+                self.stringifying_lists.pop();
+
+                result?;
+                return Ok(format!("[{}]", items.join(", ")));
+            }
+            _ => return Ok(value.to_string()),
+        };
+
+        // `print this` inside `toString` falls back to the default format.
+        if self.stringifying.iter().any(|x| Rc::ptr_eq(x, &instance)) {
+            return Ok(value.to_string());
+        }
+
+        let class = instance.borrow().class();
+        let method = match class.and_then(|x| x.borrow().find_method("toString")) {
+            Some(method) => method,
+            None => return Ok(value.to_string()),
+        };
+
+        if method.arity() != 0 {
+            return Err(InterpretError::new(format!(
+                "Method `toString` of {} can't take arguments.",
+                value
+            )));
+        }
+
+        self.stringifying.push(instance.clone());
+        let result = method
+            .bind(instance.clone())
+            .and_then(|function| function.borrow().call(self, Vec::new()));
+        self.stringifying.pop();
+
+        match result? {
+            Object::String(s) => Ok(s),
+            _ => Err(InterpretError::new(format!(
+                "Method `toString` of {} must return a string.",
+                value
+            ))),
         }
     }
 
@@ -67,13 +613,393 @@ impl Interpreter {
         name: &Token,
         hash_expr: &HashExpr,
     ) -> Result<Object, InterpretError> {
-        if let Some(distance) = self.locals.borrow().get(hash_expr).cloned() {
+        if !self.resolving {
+            self.environment.borrow().get(&name.lexeme)
+        } else if let Some(distance) = self.locals.borrow().get(hash_expr).cloned() {
             self.environment
                 .borrow()
                 .get_at(distance, &name.lexeme.clone())
         } else {
             self.globals.borrow().get(&name.lexeme.clone())
         }
+        .map_err(|err| err.or_line(name.line))
+    }
+
+    fn index(bracket: &Token, index: &Object, len: usize) -> Result<usize, InterpretError> {
+        match *index {
+            Object::Integer(n) if n >= 0 && (n as usize) < len => Ok(n as usize),
+            Object::Integer(_) => Err(InterpretError::at(bracket, "Index out of range.")),
+            Object::Number(n) if n.fract() != 0.0 => {
+                Err(InterpretError::at(bracket, "Index must be an integer."))
+            }
+            Object::Number(n) if n >= 0.0 && (n as usize) < len => Ok(n as usize),
+            Object::Number(_) => Err(InterpretError::at(bracket, "Index out of range.")),
+            _ => Err(InterpretError::at(bracket, "Index must be a number.")),
+        }
+    }
+
+    /// Exact on two integers, on floats as soon as one of the numbers is a float.
+    fn arithmetic(
+        op: &Token,
+        left: &Object,
+        right: &Object,
+        integer: fn(i64, i64) -> Option<i64>,
+        float: fn(f64, f64) -> f64,
+    ) -> Result<Object, InterpretError> {
+        match (left, right) {
+            (Object::Integer(l), Object::Integer(r)) => integer(*l, *r)
+                .map(Object::Integer)
+                .ok_or_else(|| InterpretError::at(op, "Integer overflow.")),
+            _ => match (left.as_float(), right.as_float()) {
+                (Some(l), Some(r)) => Ok(Object::Number(float(l, r))),
+                _ => Err(InterpretError::at(op, "Operators must be two numbers.")),
+            },
+        }
+    }
+
+    fn bitwise(
+        op: &Token,
+        left: &Object,
+        right: &Object,
+        fun: fn(i64, i64) -> Option<i64>,
+    ) -> Result<Object, InterpretError> {
+        match (left, right) {
+            (Object::Integer(l), Object::Integer(r)) => fun(*l, *r)
+                .map(Object::Integer)
+                .ok_or_else(|| InterpretError::at(op, "Shift out of range.")),
+            _ => Err(InterpretError::at(op, "Operators must be two integers.")),
+        }
+    }
+
+    fn compare(
+        op: &Token,
+        left: &Object,
+        right: &Object,
+        test: fn(Ordering) -> bool,
+    ) -> Result<Object, InterpretError> {
+        if left.as_float().is_none() || right.as_float().is_none() {
+            return Err(InterpretError::at(op, "Operators must be two numbers."));
+        }
+        // Comparisons with NaN are all false.
+        Ok(Object::Boolean(left.compare(right).is_some_and(test)))
+    }
+
+    /// Apply the binary operator `token_type` on two values, pointing errors at `op`.
+    fn binary(
+        &mut self,
+        op: &Token,
+        token_type: &TokenType,
+        left: Object,
+        right: Object,
+    ) -> Result<Object, InterpretError> {
+        match token_type {
+            TokenType::Minus => {
+                Interpreter::arithmetic(op, &left, &right, i64::checked_sub, |l, r| l - r)
+            }
+            TokenType::Plus => match (left, right) {
+                (l, r) if l.as_float().is_some() && r.as_float().is_some() => {
+                    Interpreter::arithmetic(op, &l, &r, i64::checked_add, |l, r| l + r)
+                }
+                (Object::String(l), Object::String(r)) => Ok(Object::String(l + &r)),
+                // 实例通过 `toString()` 转成字符串再拼接
+                (Object::String(l), r @ Object::Instance(_)) if !self.strict => {
+                    let r = self.stringify(&r).map_err(|err| err.or_at(op))?;
+                    Ok(Object::String(format!("{}{}", l, r)))
+                }
+                (l @ Object::Instance(_), Object::String(r)) if !self.strict => {
+                    let l = self.stringify(&l).map_err(|err| err.or_at(op))?;
+                    Ok(Object::String(format!("{}{}", l, r)))
+                }
+                _ => Err(InterpretError::at(
+                    op,
+                    "Operators must be two numbers or strings.",
+                )),
+            },
+            TokenType::Interpolation => {
+                let left = self.stringify(&left).map_err(|err| err.or_at(op))?;
+                let right = self.stringify(&right).map_err(|err| err.or_at(op))?;
+                Ok(Object::String(format!("{}{}", left, right)))
+            }
+            TokenType::Slash => match (left.as_float(), right.as_float()) {
+                (Some(_), Some(r)) if r == 0.0 && !self.strict => {
+                    Err(InterpretError::at(op, "Division by zero."))
+                }
+                (Some(l), Some(r)) => Ok(Object::Number(l / r)),
+                _ => Err(InterpretError::at(op, "Operators must be two numbers.")),
+            },
+            TokenType::TildeSlash => match (left, right) {
+                (_, r) if r.as_float() == Some(0.0) => {
+                    Err(InterpretError::at(op, "Division by zero."))
+                }
+                (Object::Integer(l), Object::Integer(r)) => l
+                    .checked_div(r)
+                    .map(Object::Integer)
+                    .ok_or_else(|| InterpretError::at(op, "Integer overflow.")),
+                (l, r) => match (l.as_float(), r.as_float()) {
+                    (Some(l), Some(r)) => {
+                        let quotient = (l / r).trunc();
+                        // `i64::MAX as f64` is 2^63, already out of range.
+                        match quotient >= i64::MIN as f64 && quotient < i64::MAX as f64 {
+                            true => Ok(Object::Integer(quotient as i64)),
+                            false => Err(InterpretError::at(op, "Integer overflow.")),
+                        }
+                    }
+                    _ => Err(InterpretError::at(op, "Operators must be two numbers.")),
+                },
+            },
+            TokenType::Percent => match right.as_float() {
+                Some(0.0) => Err(InterpretError::at(op, "Division by zero.")),
+                _ => Interpreter::arithmetic(op, &left, &right, i64::checked_rem, |l, r| l % r),
+            },
+            TokenType::Star => {
+                Interpreter::arithmetic(op, &left, &right, i64::checked_mul, |l, r| l * r)
+            }
+            TokenType::Ampersand => Interpreter::bitwise(op, &left, &right, |l, r| Some(l & r)),
+            TokenType::Pipe => Interpreter::bitwise(op, &left, &right, |l, r| Some(l | r)),
+            TokenType::Caret => Interpreter::bitwise(op, &left, &right, |l, r| Some(l ^ r)),
+            TokenType::LessLess => Interpreter::bitwise(op, &left, &right, |l, r| {
+                u32::try_from(r).ok().and_then(|r| l.checked_shl(r))
+            }),
+            TokenType::GreaterGreater => Interpreter::bitwise(op, &left, &right, |l, r| {
+                u32::try_from(r).ok().and_then(|r| l.checked_shr(r))
+            }),
+            TokenType::StarStar => match (left, right) {
+                (Object::Integer(l), Object::Integer(r)) if r >= 0 => u32::try_from(r)
+                    .ok()
+                    .and_then(|r| l.checked_pow(r))
+                    .map(Object::Integer)
+                    .ok_or_else(|| InterpretError::at(op, "Integer overflow.")),
+                (l, r) => match (l.as_float(), r.as_float()) {
+                    (Some(l), Some(r)) => Ok(Object::Number(l.powf(r))),
+                    _ => Err(InterpretError::at(op, "Operators must be two numbers.")),
+                },
+            },
+            TokenType::Greater => Interpreter::compare(op, &left, &right, Ordering::is_gt),
+            TokenType::GreaterEqual => Interpreter::compare(op, &left, &right, Ordering::is_ge),
+            TokenType::Less => Interpreter::compare(op, &left, &right, Ordering::is_lt),
+            TokenType::LessEqual => Interpreter::compare(op, &left, &right, Ordering::is_le),
+            TokenType::BangEqual => Ok(Object::Boolean(left != right)),
+            TokenType::EqualEqual => Ok(Object::Boolean(left == right)),
+            _ => Err(InterpretError::new("Unreachable error!")),
+        }
+    }
+
+    /// The value stored by `++`, `--` or a compound assignment, and the value of the expression.
+    fn update(
+        &mut self,
+        expr: &UpdateExpr,
+        old: Object,
+    ) -> Result<(Object, Object), InterpretError> {
+        let operand = match expr.value {
+            Some(ref value) => self.evaluate(value)?,
+            None if old.as_float().is_none() => {
+                return Err(InterpretError::at(&expr.op, "Operator must be a number."))
+            }
+            None => Object::Integer(1),
+        };
+        let token_type = match expr.op.token_type {
+            TokenType::PlusEqual | TokenType::PlusPlus => TokenType::Plus,
+            TokenType::MinusEqual | TokenType::MinusMinus => TokenType::Minus,
+            TokenType::StarEqual => TokenType::Star,
+            TokenType::SlashEqual => TokenType::Slash,
+            TokenType::PercentEqual => TokenType::Percent,
+            _ => return Err(InterpretError::new("Unreachable error!")),
+        };
+        let value = self.binary(&expr.op, &token_type, old.clone(), operand)?;
+
+        let result = match (&expr.value, expr.prefix) {
+            (Some(_), _) => Object::Nil,
+            (None, true) => value.clone(),
+            (None, false) => old,
+        };
+        Ok((value, result))
+    }
+
+    /// Evaluate a property access, call or index, `None` when an optional chain it is part of
+    /// stopped at nil: in `a?.b.c()`, a nil `a` skips `.c` and the call too.
+    fn chain(&mut self, hash_expr: &HashExpr) -> Result<Option<Object>, InterpretError> {
+        match &hash_expr.expr {
+            Expr::Get(expr) => match self.chain(&expr.object)? {
+                Some(Object::Nil) if expr.optional => Ok(None),
+                Some(object) => self.get_property(&object, &expr.name).map(Some),
+                None => Ok(None),
+            },
+            Expr::Call(expr) => match self.chain(&expr.callee)? {
+                Some(callee) => self.call(expr, callee).map(Some),
+                None => Ok(None),
+            },
+            Expr::Index(expr) => match self.chain(&expr.object)? {
+                Some(object) => {
+                    let index = self.evaluate(&expr.index)?;
+                    Interpreter::get_element(&object, &expr.bracket, &index).map(Some)
+                }
+                None => Ok(None),
+            },
+            _ => self.evaluate(hash_expr).map(Some),
+        }
+    }
+
+    fn call(&mut self, expr: &CallExpr, callee: Object) -> Result<Object, InterpretError> {
+        let mut arguments: Vec<Object> = Vec::new();
+        for argument in &expr.arguments {
+            arguments.push(self.evaluate(argument)?)
+        }
+
+        match callee {
+            Object::Function(function) => {
+                if arguments.len() != function.borrow().arity() {
+                    return Err(InterpretError::at(
+                        &expr.paren,
+                        format!(
+                            "Expected {} arguments but got {}.",
+                            function.borrow().arity(),
+                            arguments.len()
+                        ),
+                    ));
+                }
+                function
+                    .borrow()
+                    .call(self, arguments)
+                    .map_err(|err| err.or_at(&expr.paren))
+            }
+            Object::Class(class) => {
+                if arguments.len() != class.borrow().arity() {
+                    return Err(InterpretError::at(
+                        &expr.paren,
+                        format!(
+                            "Expected {} arguments but got {}.",
+                            class.borrow().arity(),
+                            arguments.len()
+                        ),
+                    ));
+                }
+                class
+                    .borrow()
+                    .call(self, arguments)
+                    .map_err(|err| err.or_line(expr.paren.line))
+            }
+            _ => Err(InterpretError::at(
+                &expr.paren,
+                "Can only call functions and classes.",
+            )),
+        }
+    }
+
+    fn assign_variable(
+        &mut self,
+        name: &Token,
+        hash_expr: &HashExpr,
+        value: Object,
+    ) -> Result<(), InterpretError> {
+        if !self.resolving {
+            self.environment.borrow_mut().set(&name.lexeme, value)
+        } else if let Some(distance) = self.locals.borrow().get(hash_expr).cloned() {
+            self.environment
+                .borrow_mut()
+                .set_at(distance, &name.lexeme, value)
+        } else {
+            self.globals.borrow_mut().set(&name.lexeme, value)
+        }
+        .map_err(|err| err.or_line(name.line))
+    }
+
+    fn get_property(&self, object: &Object, name: &Token) -> Result<Object, InterpretError> {
+        match object {
+            Object::Module(module) => module
+                .borrow()
+                .get(&name.lexeme)
+                .map_err(|err| err.or_line(name.line)),
+            Object::Instance(instance) => instance
+                .borrow()
+                .get(&name.lexeme)
+                .map_err(|err| err.or_line(name.line)),
+            Object::Class(class) if !self.strict => class
+                .borrow()
+                .get(&name.lexeme)
+                .map_err(|err| err.or_line(name.line)),
+            _ => Err(InterpretError::at(name, "Only instances have properties.")),
+        }
+    }
+
+    fn set_property(
+        &self,
+        object: &Object,
+        name: &Token,
+        value: Object,
+    ) -> Result<(), InterpretError> {
+        match object {
+            Object::Module(module) => module
+                .borrow_mut()
+                .set(&name.lexeme, value)
+                .map_err(|err| err.or_line(name.line)),
+            Object::Instance(instance) => instance.borrow_mut().set(&name.lexeme, value),
+            Object::Class(class) if !self.strict => class.borrow_mut().set(&name.lexeme, value),
+            _ => Err(InterpretError::at(name, "Only instances have fields.")),
+        }
+    }
+
+    fn get_element(
+        object: &Object,
+        bracket: &Token,
+        index: &Object,
+    ) -> Result<Object, InterpretError> {
+        match object {
+            Object::List(list) => {
+                let i = Interpreter::index(bracket, index, list.borrow().len())?;
+                Ok(list.borrow()[i].clone())
+            }
+            Object::String(s) => {
+                let i = Interpreter::index(bracket, index, s.chars().count())?;
+                Ok(Object::String(s.chars().nth(i).unwrap().to_string()))
+            }
+            _ => Err(InterpretError::at(
+                bracket,
+                "Only lists and strings can be indexed.",
+            )),
+        }
+    }
+
+    fn set_element(
+        object: &Object,
+        bracket: &Token,
+        index: &Object,
+        value: Object,
+    ) -> Result<(), InterpretError> {
+        match object {
+            Object::List(list) => {
+                let i = Interpreter::index(bracket, index, list.borrow().len())?;
+                list.borrow_mut()[i] = value;
+                Ok(())
+            }
+            _ => Err(InterpretError::at(
+                bracket,
+                "Only list elements can be assigned.",
+            )),
+        }
+    }
+
+    /// The elements of a list destructured into `count` names or targets.
+    fn elements(
+        bracket: &Token,
+        value: &Object,
+        count: usize,
+    ) -> Result<Vec<Object>, InterpretError> {
+        match value {
+            Object::List(list) if list.borrow().len() == count => Ok(list.borrow().clone()),
+            Object::List(list) => Err(InterpretError::at(
+                bracket,
+                format!(
+                    "Expected {} element{} to destructure but got {}.",
+                    count,
+                    if count == 1 { "" } else { "s" },
+                    list.borrow().len()
+                ),
+            )),
+            _ => Err(InterpretError::at(
+                bracket,
+                "Only lists can be destructured with `[...]`.",
+            )),
+        }
     }
 
     fn evaluate(&mut self, hash_expr: &HashExpr) -> Result<Object, InterpretError> {
@@ -81,33 +1007,31 @@ impl Interpreter {
     }
 
     fn execute(&mut self, stmt: &Stmt) -> Result<(), InterpretError> {
+        if let Some(ref hook) = self.hook {
+            if let Some(line) = stmt.line() {
+                hook.borrow_mut().statement(self, line)?;
+            }
+        }
         self.visit_stmt(stmt)?;
         Ok(())
     }
 
     pub fn execute_block(
         &mut self,
-        statements: &Vec<Rc<Stmt>>,
+        statements: &[Rc<Stmt>],
         environment: Rc<RefCell<Environment>>,
-    ) -> Result<Object, InterpretError> {
+    ) -> Result<(), InterpretError> {
         let previous = self.environment.clone();
         self.environment = environment;
-        for statement in statements {
-            if let Err(err) = self.execute(&statement) {
-                return match err {
-                    InterpretError::Return(value) => {
-                        self.environment = previous;
-                        Ok(value)
-                    }
-                    InterpretError::Error(message) => {
-                        self.environment = previous;
-                        Err(InterpretError::Error(message))
-                    }
-                };
-            }
-        }
+
+        // Whatever unwinds out of the block (`return`, `throw` or a runtime error),
+        // the caller's environment must be restored before it propagates.
+        let result = statements
+            .iter()
+            .try_for_each(|statement| self.execute(statement));
+
         self.environment = previous;
-        Ok(Object::Nil)
+        result
     }
 }
 
@@ -122,101 +1046,56 @@ impl Visitor<Object, InterpretError> for Interpreter {
                 match expr.op.token_type {
                     TokenType::Minus => match right {
                         Object::Number(n) => Ok(Object::Number(-n)),
-                        _ => Err(InterpretError::Error(format!(
-                            "[line {}] <{:?}> : Operator must be a number.",
-                            expr.op.line, expr.op
-                        ))),
+                        Object::Integer(n) => n
+                            .checked_neg()
+                            .map(Object::Integer)
+                            .ok_or_else(|| InterpretError::at(&expr.op, "Integer overflow.")),
+                        _ => Err(InterpretError::at(&expr.op, "Operator must be a number.")),
                     },
-                    TokenType::Bang => Ok(Object::Boolean(!right.is_truthy())),
-                    _ => Err(InterpretError::Error("Unreachable error!".to_string())),
+                    TokenType::Tilde => match right {
+                        Object::Integer(n) => Ok(Object::Integer(!n)),
+                        _ => Err(InterpretError::at(&expr.op, "Operator must be an integer.")),
+                    },
+                    TokenType::Bang => Ok(Object::Boolean(!self.is_truthy(&right))),
+                    _ => Err(InterpretError::new("Unreachable error!")),
                 }
             }
             Expr::Binary(expr) => {
                 let left = self.evaluate(&expr.left)?;
                 let right = self.evaluate(&expr.right)?;
-
-                match expr.op.token_type {
-                    TokenType::Minus => match (left, right) {
-                        (Object::Number(l), Object::Number(r)) => Ok(Object::Number(l - r)),
-                        _ => Err(InterpretError::Error(format!(
-                            "[line {}] <{:?}> : Operators must be two numbers.",
-                            expr.op.line, expr.op
-                        ))),
-                    },
-                    TokenType::Plus => match (left, right) {
-                        (Object::Number(l), Object::Number(r)) => Ok(Object::Number(l + r)),
-                        (Object::String(l), Object::String(r)) => {
-                            Ok(Object::String(format!("{}{}", l, r)))
-                        }
-                        _ => Err(InterpretError::Error(format!(
-                            "[line {}] <{:?}> : Operators must be two numbers or strings.",
-                            expr.op.line, expr.op
-                        ))),
-                    },
-                    TokenType::Slash => match (left, right) {
-                        (Object::Number(l), Object::Number(r)) => match r {
-                            0.0 => Err(InterpretError::Error(format!(
-                                "[line {}] <{:?}> : Division by zero.",
-                                expr.op.line, expr.op
-                            ))),
-                            _ => Ok(Object::Number(l / r)),
-                        },
-                        _ => Err(InterpretError::Error(format!(
-                            "[line {}] <{:?}> : Operators must be two numbers.",
-                            expr.op.line, expr.op
-                        ))),
-                    },
-                    TokenType::Star => match (left, right) {
-                        (Object::Number(l), Object::Number(r)) => Ok(Object::Number(l * r)),
-                        _ => Err(InterpretError::Error(format!(
-                            "[line {}] <{:?}> : Operators must be two numbers.",
-                            expr.op.line, expr.op
-                        ))),
-                    },
-                    TokenType::Greater => match (left, right) {
-                        (Object::Number(l), Object::Number(r)) => Ok(Object::Boolean(l > r)),
-                        _ => Err(InterpretError::Error(format!(
-                            "[line {}] <{:?}> : Operators must be two numbers.",
-                            expr.op.line, expr.op
-                        ))),
-                    },
-                    TokenType::GreaterEqual => match (left, right) {
-                        (Object::Number(l), Object::Number(r)) => Ok(Object::Boolean(l >= r)),
-                        _ => Err(InterpretError::Error(format!(
-                            "[line {}] <{:?}> : Operators must be two numbers.",
-                            expr.op.line, expr.op
-                        ))),
-                    },
-                    TokenType::Less => match (left, right) {
-                        (Object::Number(l), Object::Number(r)) => Ok(Object::Boolean(l < r)),
-                        _ => Err(InterpretError::Error(format!(
-                            "[line {}] <{:?}> : Operators must be two numbers.",
-                            expr.op.line, expr.op
-                        ))),
-                    },
-                    TokenType::LessEqual => match (left, right) {
-                        (Object::Number(l), Object::Number(r)) => Ok(Object::Boolean(l <= r)),
-                        _ => Err(InterpretError::Error(format!(
-                            "[line {}] <{:?}> : Operators must be two numbers.",
-                            expr.op.line, expr.op
-                        ))),
-                    },
-                    TokenType::BangEqual => Ok(Object::Boolean(left != right)),
-                    TokenType::EqualEqual => Ok(Object::Boolean(left == right)),
-                    _ => Err(InterpretError::Error("Unreachable error!".to_string())),
-                }
+                self.binary(&expr.op, &expr.op.token_type, left, right)
             }
             Expr::Variable(expr) => Ok(self.lookup_variable(&expr.name, hash_expr))?,
             Expr::Assign(expr) => {
                 let value = self.evaluate(&expr.value)?;
-                if let Some(distance) = self.locals.borrow().get(hash_expr).cloned() {
-                    self.environment
-                        .borrow_mut()
-                        .set_at(distance, &expr.name.lexeme, value)?
-                } else {
-                    self.globals.borrow_mut().set(&expr.name.lexeme, value)?
+                // In jlox an assignment is worth the value assigned.
+                let result = match self.strict {
+                    true => value.clone(),
+                    false => Object::Nil,
+                };
+                self.assign_variable(&expr.name, hash_expr, value)?;
+                Ok(result)
+            }
+            Expr::AssignList(expr) => {
+                // 右边先求值，`[a, b] = [b, a]` 才能交换
+                let value = self.evaluate(&expr.value)?;
+                let values = Interpreter::elements(&expr.bracket, &value, expr.targets.len())?;
+                for (target, element) in expr.targets.iter().zip(values) {
+                    match &target.expr {
+                        Expr::Variable(t) => self.assign_variable(&t.name, target, element)?,
+                        Expr::Get(t) => {
+                            let object = self.evaluate(&t.object)?;
+                            self.set_property(&object, &t.name, element)?;
+                        }
+                        Expr::Index(t) => {
+                            let object = self.evaluate(&t.object)?;
+                            let index = self.evaluate(&t.index)?;
+                            Interpreter::set_element(&object, &t.bracket, &index, element)?;
+                        }
+                        _ => unreachable!(),
+                    }
                 }
-                Ok(Object::Nil)
+                Ok(value)
             }
             Expr::Logical(expr) => {
                 let left = self.evaluate(&expr.left)?;
@@ -224,101 +1103,146 @@ impl Visitor<Object, InterpretError> for Interpreter {
                 match expr.op.token_type {
                     TokenType::Or => {
                         // 或：第一个为真，就为真
-                        if left.is_truthy() {
+                        if self.is_truthy(&left) {
                             return Ok(left);
                         }
                     }
                     TokenType::And => {
                         // 与：第一个为假，就为假
-                        if !left.is_truthy() {
+                        if !self.is_truthy(&left) {
+                            return Ok(left);
+                        }
+                    }
+                    TokenType::QuestionQuestion => {
+                        // 空值合并：第一个不为 nil，就为第一个
+                        if !matches!(left, Object::Nil) {
                             return Ok(left);
                         }
                     }
-                    _ => return Err(InterpretError::Error("Unreachable error!".to_string())),
+                    _ => return Err(InterpretError::new("Unreachable error!")),
                 }
 
                 self.evaluate(&expr.right)
             }
-            Expr::Call(expr) => {
-                let callee = self.evaluate(&expr.callee)?;
-
-                let mut arguments: Vec<Object> = Vec::new();
-                for argument in &expr.arguments {
-                    arguments.push(self.evaluate(argument)?)
+            Expr::Call(_) | Expr::Get(_) | Expr::Index(_) => {
+                Ok(self.chain(hash_expr)?.unwrap_or(Object::Nil))
+            }
+            Expr::Conditional(expr) => {
+                let condition = self.evaluate(&expr.condition)?;
+                match self.is_truthy(&condition) {
+                    true => self.evaluate(&expr.then_branch),
+                    false => self.evaluate(&expr.else_branch),
                 }
-
-                match callee {
-                    Object::Function(function) => {
-                        if arguments.len() != function.borrow().arity() {
-                            return Err(InterpretError::Error(format!(
-                                "[line {}] <{:?}> : Expected {} arguments but got {}.",
-                                expr.paren.line,
-                                expr.paren,
-                                function.borrow().arity(),
-                                arguments.len()
-                            )));
-                        }
-                        function.borrow().call(self, arguments)
-                    }
-                    Object::Class(class) => {
-                        if arguments.len() != class.borrow().arity() {
-                            return Err(InterpretError::Error(format!(
-                                "[line {}] <{:?}> : Expected {} arguments but got {}.",
-                                expr.paren.line,
-                                expr.paren,
-                                class.borrow().arity(),
-                                arguments.len()
-                            )));
-                        }
-                        class.borrow().call(self, arguments)
-                    }
-                    _ => Err(InterpretError::Error(format!(
-                        "[line {}] <{:?}> : Can only call functions and classes.",
-                        expr.paren.line, expr.paren
-                    ))),
-                }
-            }
-            Expr::Get(expr) => match self.evaluate(&expr.object)? {
-                Object::Instance(instance) => instance.borrow().get(&expr.name.lexeme),
-                Object::Class(class) => class.borrow().get(&expr.name.lexeme),
-                _ => Err(InterpretError::Error(format!(
-                    "[line {}] <{:?}> : Only instances have properties.",
-                    expr.name.line, expr.name
-                ))),
-            },
+            }
             Expr::Set(expr) => match self.evaluate(&expr.object)? {
+                Object::Module(module) => {
+                    let value = self.evaluate(&expr.value)?;
+                    module
+                        .borrow_mut()
+                        .set(&expr.name.lexeme, value)
+                        .map_err(|err| err.or_line(expr.name.line))?;
+                    Ok(Object::Nil)
+                }
                 Object::Instance(instance) => {
                     let value = self.evaluate(&expr.value)?;
+                    let result = match self.strict {
+                        true => value.clone(),
+                        false => Object::Nil,
+                    };
                     instance.borrow_mut().set(&expr.name.lexeme, value)?;
-                    Ok(Object::Nil)
+                    Ok(result)
                 }
-                Object::Class(class) => {
+                Object::Class(class) if !self.strict => {
                     let value = self.evaluate(&expr.value)?;
                     class.borrow_mut().set(&expr.name.lexeme, value)?;
                     Ok(Object::Nil)
                 }
-                _ => Err(InterpretError::Error(format!(
-                    "[line {}] <{:?}> : Only instances have fields.",
-                    expr.name.line, expr.name
-                ))),
+                _ => Err(InterpretError::at(
+                    &expr.name,
+                    "Only instances have fields.",
+                )),
             },
             Expr::This(expr) => self.lookup_variable(&expr.keyword, hash_expr),
+            Expr::List(expr) => {
+                let mut elements: Vec<Object> = Vec::new();
+                for element in &expr.elements {
+                    elements.push(self.evaluate(element)?);
+                }
+                Ok(Object::List(Rc::new(RefCell::new(elements))))
+            }
+            // The parts of the target are evaluated once, `a[i()] += 1` calls `i` once.
+            Expr::Update(expr) => match &expr.target.expr {
+                Expr::Variable(target) => {
+                    let old = self.lookup_variable(&target.name, &expr.target)?;
+                    let (value, result) = self.update(expr, old)?;
+                    self.assign_variable(&target.name, &expr.target, value)?;
+                    Ok(result)
+                }
+                Expr::Get(target) => {
+                    let object = self.evaluate(&target.object)?;
+                    let old = self.get_property(&object, &target.name)?;
+                    let (value, result) = self.update(expr, old)?;
+                    self.set_property(&object, &target.name, value)?;
+                    Ok(result)
+                }
+                Expr::Index(target) => {
+                    let object = self.evaluate(&target.object)?;
+                    let index = self.evaluate(&target.index)?;
+                    let old = Interpreter::get_element(&object, &target.bracket, &index)?;
+                    let (value, result) = self.update(expr, old)?;
+                    Interpreter::set_element(&object, &target.bracket, &index, value)?;
+                    Ok(result)
+                }
+                _ => Err(InterpretError::new("Unreachable error!")),
+            },
+            Expr::SetIndex(expr) => match self.evaluate(&expr.object)? {
+                Object::List(list) => {
+                    let index = self.evaluate(&expr.index)?;
+                    let value = self.evaluate(&expr.value)?;
+                    let i = Interpreter::index(&expr.bracket, &index, list.borrow().len())?;
+                    list.borrow_mut()[i] = value;
+                    Ok(Object::Nil)
+                }
+                _ => Err(InterpretError::at(
+                    &expr.bracket,
+                    "Only list elements can be assigned.",
+                )),
+            },
             Expr::Super(expr) => {
-                if let Some(distance) = self.locals.borrow().get(hash_expr).cloned() {
-                    let superclass = self.environment.borrow().get_at(distance, "super")?;
-                    let object = self.environment.borrow().get_at(distance - 1, "this")?;
+                let distance = self.locals.borrow().get(hash_expr).cloned();
+                let found = match distance {
+                    _ if !self.resolving => {
+                        let environment = self.environment.borrow();
+                        Some((environment.get("super")?, environment.get("this")?))
+                    }
+                    Some(distance) => {
+                        let environment = self.environment.borrow();
+                        Some((
+                            environment.get_at(distance, "super")?,
+                            environment.get_at(distance - 1, "this")?,
+                        ))
+                    }
+                    None => None,
+                };
+                if let Some((superclass, object)) = found {
+                    if let Object::Nil = superclass {
+                        return Err(InterpretError::at(
+                            &expr.keyword,
+                            "Can't use 'super' in a trait method of a class with no superclass.",
+                        ));
+                    }
                     if let (Object::Class(class), Object::Instance(instance)) = (superclass, object)
                     {
                         if let Some(method) = class.borrow().find_method(&expr.method.lexeme) {
                             return Ok(Object::Function(method.bind(instance)?));
                         }
-                        return Err(InterpretError::Error(format!(
-                            "[line {}] <{:?}> : Undefined superclass method '{}'.",
-                            expr.method.line, expr.method, expr.method.lexeme
-                        )));
+                        return Err(InterpretError::at(
+                            &expr.method,
+                            format!("Undefined superclass method '{}'.", expr.method.lexeme),
+                        ));
                     }
                 }
-                Err(InterpretError::Error("Unreachable error!".to_string()))
+                Err(InterpretError::new("Unreachable error!"))
             }
         }
     }
@@ -330,7 +1254,10 @@ impl Visitor<Object, InterpretError> for Interpreter {
             }
             Stmt::Print(stmt) => {
                 let value = self.evaluate(&stmt.expression)?;
-                println!("{}", value);
+                let text = self
+                    .stringify(&value)
+                    .map_err(|err| err.or_at(&stmt.keyword))?;
+                self.write(&format!("{}\n", text));
                 Ok(())
             }
             Stmt::Var(stmt) => {
@@ -338,9 +1265,30 @@ impl Visitor<Object, InterpretError> for Interpreter {
                 if let Some(ref initializer) = stmt.initializer {
                     value = self.evaluate(initializer)?
                 }
-                self.environment
-                    .borrow_mut()
-                    .define(stmt.name.lexeme.clone(), value);
+                let name = stmt.name.lexeme.clone();
+                match stmt.is_constant() {
+                    true => self.environment.borrow_mut().define_constant(name, value),
+                    false => self.environment.borrow_mut().define(name, value),
+                }
+                Ok(())
+            }
+            Stmt::Destructure(stmt) => {
+                let value = self.evaluate(&stmt.initializer)?;
+                let values = match stmt.is_list() {
+                    true => Interpreter::elements(&stmt.pattern, &value, stmt.names.len())?,
+                    false => stmt
+                        .names
+                        .iter()
+                        .map(|name| self.get_property(&value, name))
+                        .collect::<Result<Vec<_>, _>>()?,
+                };
+                for (name, value) in stmt.names.iter().zip(values) {
+                    let name = name.lexeme.clone();
+                    match stmt.is_constant() {
+                        true => self.environment.borrow_mut().define_constant(name, value),
+                        false => self.environment.borrow_mut().define(name, value),
+                    }
+                }
                 Ok(())
             }
             Stmt::Block(stmt) => {
@@ -351,7 +1299,8 @@ impl Visitor<Object, InterpretError> for Interpreter {
                 Ok(())
             }
             Stmt::If(stmt) => {
-                if self.evaluate(&stmt.condition)?.is_truthy() {
+                let condition = self.evaluate(&stmt.condition)?;
+                if self.is_truthy(&condition) {
                     self.execute(&stmt.then_branch)?
                 } else if let Some(ref else_branch) = stmt.else_branch {
                     self.execute(else_branch)?
@@ -359,20 +1308,97 @@ impl Visitor<Object, InterpretError> for Interpreter {
                 Ok(())
             }
             Stmt::While(stmt) => {
-                while self.evaluate(&stmt.condition)?.is_truthy() {
+                loop {
+                    let condition = self.evaluate(&stmt.condition)?;
+                    if !self.is_truthy(&condition) {
+                        break;
+                    }
                     self.execute(&stmt.body)?
                 }
                 Ok(())
             }
             Stmt::Function(stmt) => {
-                let function =
-                    Function::new(Rc::new(stmt.clone()), self.environment.clone(), false);
+                let function = Function::new(
+                    Rc::new(stmt.clone()),
+                    self.environment.clone(),
+                    self.globals.clone(),
+                    false,
+                );
                 self.environment.borrow_mut().define(
                     stmt.name.lexeme.clone(),
                     Object::Function(Rc::new(RefCell::new(function))),
                 );
                 Ok(())
             }
+            Stmt::Import(stmt) => {
+                let module = self.import_module(stmt)?;
+
+                if let Some(ref name) = stmt.name {
+                    self.environment
+                        .borrow_mut()
+                        .define(name.lexeme.clone(), Object::Module(module.clone()));
+                }
+
+                for name in &stmt.names {
+                    let value = module
+                        .borrow()
+                        .get(&name.lexeme)
+                        .map_err(|err| err.or_line(name.line))?;
+                    self.environment
+                        .borrow_mut()
+                        .define(name.lexeme.clone(), value);
+                }
+
+                Ok(())
+            }
+            Stmt::Throw(stmt) => {
+                let value = self.evaluate(&stmt.value)?;
+                if self.is_error_instance(&value) {
+                    if let Object::Instance(ref instance) = value {
+                        if !instance.borrow().field_names().iter().any(|x| x == "line") {
+                            let line = Object::Integer(stmt.keyword.line as i64);
+                            instance.borrow_mut().set("line", line)?;
+                        }
+                    }
+                }
+                Err(InterpretError::Throw(value, stmt.keyword.line))
+            }
+            Stmt::Test(stmt) => {
+                let selected = match (&stmt.name.literal, &self.test) {
+                    (Object::String(name), Some(test)) => name == test,
+                    _ => false,
+                };
+                if selected {
+                    self.execute_block(
+                        &stmt.body,
+                        Environment::new(Some(self.environment.clone())),
+                    )?;
+                }
+                Ok(())
+            }
+            Stmt::Try(stmt) => {
+                let mut result = self
+                    .execute_block(&stmt.body, Environment::new(Some(self.environment.clone())));
+
+                if let Some(ref handler) = stmt.handler {
+                    result = match result {
+                        Err(InterpretError::Throw(value, _)) => self.execute_catch(handler, value),
+                        Err(InterpretError::Error(error)) => self
+                            .error_object(error)
+                            .and_then(|value| self.execute_catch(handler, value)),
+                        result => result,
+                    };
+                }
+
+                if matches!(result, Err(InterpretError::Abort(_))) {
+                    return result;
+                }
+                if let Some(ref finally) = stmt.finally {
+                    self.execute_block(finally, Environment::new(Some(self.environment.clone())))?;
+                }
+
+                result
+            }
             Stmt::Return(stmt) => {
                 let mut value = Object::Nil;
                 if let Some(ref expr) = stmt.value {
@@ -385,31 +1411,52 @@ impl Visitor<Object, InterpretError> for Interpreter {
                 if let Some(ref hash_expr) = stmt.superclass {
                     let value = self.evaluate(&hash_expr)?;
                     match value {
-                        Object::Class(class) => {
-                            self.environment = Environment::new(Some(self.environment.clone()));
-                            self.environment
-                                .borrow_mut()
-                                .define("super".to_string(), Object::Class(class.clone()));
-                            superclass = Some(class);
+                        Object::Class(class) => superclass = Some(class),
+                        _ => {
+                            return if let Expr::Variable(ref expr) = hash_expr.expr {
+                                Err(InterpretError::Error(RuntimeError {
+                                    line: Some(expr.name.line),
+                                    location: Some(format!("{:?}", expr)),
+                                    message: "Superclass must be a class.".to_string(),
+                                }))
+                            } else {
+                                Err(InterpretError::new("Unreachable error!"))
+                            }
                         }
+                    }
+                }
+
+                let mut mixins: Vec<Rc<Trait>> = Vec::new();
+                for hash_expr in &stmt.traits {
+                    match self.evaluate(hash_expr)? {
+                        Object::Trait(mixin) => mixins.push(mixin),
                         _ => {
                             return if let Expr::Variable(ref expr) = hash_expr.expr {
-                                Err(InterpretError::Error(format!(
-                                    "[line {}] <{:?}> : Superclass must be a class.",
-                                    expr.name.line, expr
-                                )))
+                                Err(InterpretError::Error(RuntimeError {
+                                    line: Some(expr.name.line),
+                                    location: Some(format!("{:?}", expr)),
+                                    message: "Can only mix in traits.".to_string(),
+                                }))
                             } else {
-                                Err(InterpretError::Error("Unreachable error!".to_string()))
+                                Err(InterpretError::new("Unreachable error!"))
                             }
                         }
                     }
                 }
 
+                if let Some(ref class) = superclass {
+                    self.environment = Environment::new(Some(self.environment.clone()));
+                    self.environment
+                        .borrow_mut()
+                        .define("super".to_string(), Object::Class(class.clone()));
+                }
+
                 let mut methods: HashMap<String, Rc<Function>> = HashMap::new();
                 for method in &stmt.methods {
                     let function = Function::new(
                         Rc::new(method.clone()),
                         self.environment.clone(),
+                        self.globals.clone(),
                         method.name.lexeme == "init",
                     );
                     methods.insert(method.name.lexeme.clone(), Rc::new(function));
@@ -421,12 +1468,67 @@ impl Visitor<Object, InterpretError> for Interpreter {
                     }
                 }
 
+                // Methods declared in the class body win over trait methods, but two traits
+                // providing the same method is ambiguous.
+                let mut providers: HashMap<String, Rc<Token>> = HashMap::new();
+                for mixin in mixins {
+                    let closure = Environment::new(Some(mixin.closure.clone()));
+                    closure.borrow_mut().define(
+                        "super".to_string(),
+                        superclass.clone().map_or(Object::Nil, Object::Class),
+                    );
+
+                    for (name, declaration) in &mixin.methods {
+                        if stmt.methods.iter().any(|x| x.name.lexeme == *name) {
+                            continue;
+                        }
+
+                        if let Some(provider) = providers.get(name) {
+                            return Err(InterpretError::at(
+                                &stmt.name,
+                                format!(
+                                    "Method `{}` is provided by both traits `{}` and `{}`.",
+                                    name, provider.lexeme, mixin.name.lexeme
+                                ),
+                            ));
+                        }
+
+                        providers.insert(name.clone(), mixin.name.clone());
+                        methods.insert(
+                            name.clone(),
+                            Rc::new(Function::new(
+                                declaration.clone(),
+                                closure.clone(),
+                                mixin.globals.clone(),
+                                name == "init",
+                            )),
+                        );
+                    }
+                }
+
                 let class = Class::new(stmt.name.clone(), superclass, methods);
                 self.environment
                     .borrow_mut()
                     .define(stmt.name.lexeme.clone(), Object::Class(class));
                 Ok(())
             }
+            Stmt::Trait(stmt) => {
+                let mut methods: HashMap<String, Rc<FunctionStmt>> = HashMap::new();
+                for method in &stmt.methods {
+                    methods.insert(method.name.lexeme.clone(), Rc::new(method.clone()));
+                }
+
+                let mixin = Trait::new(
+                    stmt.name.clone(),
+                    methods,
+                    self.environment.clone(),
+                    self.globals.clone(),
+                );
+                self.environment
+                    .borrow_mut()
+                    .define(stmt.name.lexeme.clone(), Object::Trait(Rc::new(mixin)));
+                Ok(())
+            }
         }
     }
 }
@@ -0,0 +1,105 @@
+//! Output in the formats of jlox, the reference implementation, for the strict mode.
+
+use crate::{interpreter::RuntimeError, object::Object, scanner::CompileError, token::TokenType};
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Messages worded differently in rlox, and their jlox counterparts.
+const MESSAGES: [(&str, &str); 6] = [
+    ("Operator must be a number.", "Operand must be a number."),
+    (
+        "Operators must be two numbers.",
+        "Operands must be numbers.",
+    ),
+    (
+        "Operators must be two numbers or strings.",
+        "Operands must be two numbers or two strings.",
+    ),
+    ("Unknown character.", "Unexpected character."),
+    ("Unexpected token.", "Expect expression."),
+    (
+        "Expect '}' before class body.",
+        "Expect '}' after class body.",
+    ),
+];
+
+static QUOTED_NAME: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"`([^`]*)`").unwrap());
+
+/// The jlox wording of a message.
+pub fn message(message: &str) -> String {
+    if let Some((_, jlox)) = MESSAGES.iter().find(|(rlox, _)| *rlox == message) {
+        return jlox.to_string();
+    }
+    // `Undefined variable `a`.` and the like.
+    QUOTED_NAME.replace_all(message, "'$1'").to_string()
+}
+
+/// `[line 1] Error at 'a': message`, from an error of the scanner, parser or resolver.
+pub fn compile_error(error: &CompileError) -> String {
+    let location = match error.token {
+        Some(ref token) if token.token_type == TokenType::Eof => " at end".to_string(),
+        Some(ref token) => format!(" at '{}'", token.lexeme),
+        None => String::new(),
+    };
+    format!(
+        "[line {}] Error{}: {}",
+        error.line,
+        location,
+        message(&error.message)
+    )
+}
+
+/// The message, then the line on a line of its own.
+pub fn runtime_error(error: &RuntimeError) -> String {
+    match error.line {
+        Some(line) => format!("{}\n[line {}]", message(&error.message), line),
+        None => message(&error.message),
+    }
+}
+
+/// Numbers as Java prints doubles, without a trailing `.0`.
+pub fn number(n: f64) -> String {
+    if n.is_nan() {
+        return "NaN".to_string();
+    }
+    if n.is_infinite() {
+        return if n > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+
+    let magnitude = n.abs();
+    if magnitude == 0.0 || (1e-3..1e7).contains(&magnitude) {
+        return n.to_string();
+    }
+
+    // Java switches to `1.0E7` outside of the range above.
+    let text = format!("{:e}", n);
+    let (mantissa, exponent) = text.split_once('e').unwrap_or((&text, "0"));
+    match mantissa.contains('.') {
+        true => format!("{}E{}", mantissa, exponent),
+        false => format!("{}.0E{}", mantissa, exponent),
+    }
+}
+
+/// Functions, classes and instances as jlox prints them. Other values print the same.
+pub fn stringify(value: &Object) -> String {
+    let text = value.to_string();
+    let strip = |prefix: &str| {
+        text.strip_prefix(prefix)
+            .and_then(|x| x.strip_suffix('>'))
+            .map(|x| x.to_string())
+    };
+
+    match value {
+        Object::Number(n) => number(*n),
+        Object::Function(_) => match strip("<fun ") {
+            Some(name) => format!("<fn {}>", name),
+            None => "<native fn>".to_string(),
+        },
+        Object::Class(_) => strip("<class ").unwrap_or(text),
+        Object::Instance(_) => match strip("<instance of ") {
+            Some(name) => format!("{} instance", name),
+            None => text,
+        },
+        _ => text,
+    }
+}
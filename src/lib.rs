@@ -1,13 +1,29 @@
+pub mod ast_json;
 pub mod class;
+pub mod coverage;
+pub mod dap;
+pub mod debugger;
 pub mod environment;
 pub mod expr;
+pub mod formatter;
 pub mod function;
 pub mod instance;
 pub mod interpreter;
+pub mod jlox;
+pub mod json;
+pub mod linter;
 pub mod lox;
+pub mod lsp;
+pub mod module;
 pub mod object;
 pub mod parser;
+pub mod profiler;
+pub mod reflect;
 pub mod resolver;
 pub mod scanner;
+pub mod stdlib;
 pub mod stmt;
+pub mod symbols;
+pub mod testing;
 pub mod token;
+pub mod traits;
@@ -0,0 +1,481 @@
+use crate::{
+    expr::{Expr, HashExpr},
+    interpreter::{Interpreter, Visitor},
+    parser::parse,
+    resolver::Resolver,
+    scanner::{scan_tokens, scan_tokens_with_comments, CompileError},
+    stmt::{FunctionStmt, Stmt},
+    token::{Token, TokenType},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    rc::Rc,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(fmt, "info"),
+            Severity::Warning => write!(fmt, "warning"),
+            Severity::Error => write!(fmt, "error"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lint {
+    pub code: &'static str,
+    pub name: &'static str,
+    pub severity: Severity,
+}
+
+pub const UNUSED_VARIABLE: Lint = Lint {
+    code: "L001",
+    name: "unused-variable",
+    severity: Severity::Warning,
+};
+pub const UNUSED_PARAMETER: Lint = Lint {
+    code: "L002",
+    name: "unused-parameter",
+    severity: Severity::Warning,
+};
+pub const UNREACHABLE_CODE: Lint = Lint {
+    code: "L003",
+    name: "unreachable-code",
+    severity: Severity::Warning,
+};
+pub const SHADOWING: Lint = Lint {
+    code: "L004",
+    name: "shadowing",
+    severity: Severity::Info,
+};
+pub const UNDECLARED_ASSIGNMENT: Lint = Lint {
+    code: "L005",
+    name: "undeclared-assignment",
+    severity: Severity::Error,
+};
+pub const NOT_CALLABLE: Lint = Lint {
+    code: "L006",
+    name: "not-callable",
+    severity: Severity::Error,
+};
+pub const METHOD_WITHOUT_THIS: Lint = Lint {
+    code: "L007",
+    name: "method-without-this",
+    severity: Severity::Info,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub lint: Lint,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            fmt,
+            "[line {}:{}] {} {} ({}): {}",
+            self.line,
+            self.column,
+            self.lint.severity,
+            self.lint.code,
+            self.lint.name,
+            self.message
+        )
+    }
+}
+
+/// Check `code` for likely mistakes. Scanner, parser and resolver errors are returned as
+/// `Err`, since nothing can be said about code that doesn't compile.
+///
+/// A `// lint-ignore` comment silences every lint on its own line, or on the next line when
+/// the comment stands alone; `// lint-ignore: L001, shadowing` only silences those lints.
+pub fn lint(code: &str) -> Result<Vec<Diagnostic>, CompileError> {
+    let tokens = scan_tokens(code)?;
+    let statements = parse(tokens.clone())?;
+    let mut resolver = Resolver::new();
+    resolver.resolve(&statements)?;
+
+    let mut linter = Linter::new(tokens, Interpreter::builtin_names());
+    linter.lint(&statements, &resolver);
+
+    let suppressions = suppressions(&scan_tokens_with_comments(code)?);
+    let mut diagnostics: Vec<Diagnostic> = linter
+        .diagnostics
+        .into_iter()
+        .filter(|x| !is_suppressed(&suppressions, x))
+        .collect();
+    diagnostics.sort_by_key(|x| (x.line, x.column));
+
+    Ok(diagnostics)
+}
+
+// Lines mapped to the lints suppressed on them, `None` meaning all of them.
+type Suppressions = HashMap<usize, Option<Vec<String>>>;
+
+fn suppressions(tokens: &[Rc<Token>]) -> Suppressions {
+    let mut suppressions: Suppressions = HashMap::new();
+    let mut last_code_line = 0;
+
+    for token in tokens {
+        if token.token_type != TokenType::Comment {
+            last_code_line = token.line;
+            continue;
+        }
+
+        let text = token.lexeme.trim_start_matches('/').trim();
+        let Some(rest) = text.strip_prefix("lint-ignore") else {
+            continue;
+        };
+
+        let line = if last_code_line == token.line {
+            token.line
+        } else {
+            token.line + 1
+        };
+        let names = rest.trim().strip_prefix(':').map(|x| {
+            x.split(',')
+                .map(|x| x.trim().to_string())
+                .filter(|x| !x.is_empty())
+                .collect()
+        });
+        suppressions.insert(line, names);
+    }
+
+    suppressions
+}
+
+fn is_suppressed(suppressions: &Suppressions, diagnostic: &Diagnostic) -> bool {
+    match suppressions.get(&diagnostic.line) {
+        Some(None) => true,
+        Some(Some(names)) => names
+            .iter()
+            .any(|x| x == diagnostic.lint.code || x == diagnostic.lint.name),
+        None => false,
+    }
+}
+
+struct Linter {
+    tokens: Vec<Rc<Token>>,
+    builtins: Vec<String>,
+    // Declarations reported when the resolver finds no use of them, if they are local.
+    variables: Vec<Rc<Token>>,
+    parameters: Vec<Rc<Token>>,
+    // Names assigned to, and those of them that are only written and don't count as a use.
+    targets: Vec<Rc<Token>>,
+    writes: HashSet<*const Token>,
+    // One flag per method being visited, set once it uses `this` or `super`.
+    methods: Vec<bool>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Linter {
+    fn new(tokens: Vec<Rc<Token>>, builtins: Vec<String>) -> Linter {
+        Linter {
+            tokens,
+            builtins,
+            variables: Vec::new(),
+            parameters: Vec::new(),
+            targets: Vec::new(),
+            writes: HashSet::new(),
+            methods: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn lint(&mut self, statements: &[Rc<Stmt>], resolver: &Resolver) {
+        self.block(statements);
+
+        let mut resolved: HashSet<*const Token> = HashSet::new();
+        let mut used: HashSet<*const Token> = HashSet::new();
+        for (name, declaration) in resolver.uses() {
+            resolved.insert(Rc::as_ptr(name));
+            if !self.writes.contains(&Rc::as_ptr(name)) {
+                used.insert(Rc::as_ptr(declaration));
+            }
+        }
+
+        for local in resolver.local_declarations() {
+            if local.shadows.is_some() {
+                let message = format!(
+                    "`{}` shadows a variable of an outer scope.",
+                    local.name.lexeme
+                );
+                self.report(SHADOWING, &local.name, message);
+            }
+        }
+
+        // Top-level declarations can be used by importers, so only locals are reported unused.
+        let locals: HashSet<*const Token> = resolver
+            .local_declarations()
+            .iter()
+            .map(|x| Rc::as_ptr(&x.name))
+            .collect();
+        let declarations = self
+            .variables
+            .iter()
+            .map(|x| (UNUSED_VARIABLE, "Local variable", x.clone()))
+            .chain(
+                self.parameters
+                    .iter()
+                    .map(|x| (UNUSED_PARAMETER, "Parameter", x.clone())),
+            )
+            .collect::<Vec<_>>();
+        for (lint, what, name) in declarations {
+            let unused = locals.contains(&Rc::as_ptr(&name))
+                && !used.contains(&Rc::as_ptr(&name))
+                && !name.lexeme.starts_with('_');
+            if unused {
+                let message = format!("{} `{}` is never used.", what, name.lexeme);
+                self.report(lint, &name, message);
+            }
+        }
+
+        for name in self.targets.clone() {
+            if !resolved.contains(&Rc::as_ptr(&name)) && !self.builtins.contains(&name.lexeme) {
+                let message = format!("Assignment to undeclared variable `{}`.", name.lexeme);
+                self.report(UNDECLARED_ASSIGNMENT, &name, message);
+            }
+        }
+    }
+
+    fn report(&mut self, lint: Lint, token: &Token, message: String) {
+        self.diagnostics.push(Diagnostic {
+            lint,
+            line: token.line,
+            column: token.column,
+            message,
+        });
+    }
+
+    /// The token after the `;` that ends the statement starting with `keyword`, which is where
+    /// the next statement starts.
+    fn after_statement(&self, keyword: &Rc<Token>) -> Option<Rc<Token>> {
+        let start = self.tokens.iter().position(|x| Rc::ptr_eq(x, keyword))?;
+        let end = self.tokens[start..]
+            .iter()
+            .position(|x| x.token_type == TokenType::Semicolon)?;
+        self.tokens.get(start + end + 1).cloned()
+    }
+
+    fn block(&mut self, statements: &[Rc<Stmt>]) {
+        let mut terminator: Option<Rc<Token>> = None;
+        for statement in statements {
+            if let Some(ref keyword) = terminator {
+                let message = format!("Code after `{}` is never executed.", keyword.lexeme);
+                let start = self.after_statement(keyword).unwrap_or(keyword.clone());
+                self.report(UNREACHABLE_CODE, &start, message);
+                terminator = None;
+                // Only the first statement after it is reported, and the rest is still checked.
+                let _ = self.visit_stmt(statement);
+                continue;
+            }
+
+            terminator = match statement.as_ref() {
+                Stmt::Return(stmt) => Some(stmt.keyword.clone()),
+                Stmt::Throw(stmt) => Some(stmt.keyword.clone()),
+                _ => None,
+            };
+            let _ = self.visit_stmt(statement);
+        }
+    }
+
+    fn function(&mut self, stmt: &FunctionStmt, method: bool) {
+        self.parameters.extend(stmt.params.iter().cloned());
+
+        if method {
+            self.methods.push(false);
+        }
+        self.block(&stmt.body);
+        if method && !self.methods.pop().unwrap_or(true) && stmt.name.lexeme != "init" {
+            let message = format!(
+                "Method `{}` never uses `this` and could be a function.",
+                stmt.name.lexeme
+            );
+            self.report(METHOD_WITHOUT_THIS, &stmt.name, message);
+        }
+    }
+
+    fn assigned(&mut self, name: &Rc<Token>) {
+        self.targets.push(name.clone());
+        self.writes.insert(Rc::as_ptr(name));
+    }
+
+    fn uses_this(&mut self) {
+        if let Some(uses) = self.methods.last_mut() {
+            *uses = true;
+        }
+    }
+}
+
+impl Visitor<(), String> for Linter {
+    fn visit_expr(&mut self, hash_expr: &HashExpr) -> Result<(), String> {
+        match &hash_expr.expr {
+            Expr::Unary(expr) => self.visit_expr(&expr.right),
+            Expr::Binary(expr) => {
+                self.visit_expr(&expr.left)?;
+                self.visit_expr(&expr.right)
+            }
+            Expr::Logical(expr) => {
+                self.visit_expr(&expr.left)?;
+                self.visit_expr(&expr.right)
+            }
+            Expr::Literal(_) => Ok(()),
+            Expr::Grouping(expr) => self.visit_expr(&expr.expression),
+            Expr::Call(expr) => {
+                if let Expr::Literal(_) | Expr::List(_) = expr.callee.expr {
+                    let message = format!("`{}` can't be called.", expr.callee);
+                    self.report(NOT_CALLABLE, &expr.paren, message);
+                }
+                self.visit_expr(&expr.callee)?;
+                for argument in &expr.arguments {
+                    self.visit_expr(argument)?;
+                }
+                Ok(())
+            }
+            Expr::Variable(_) => Ok(()),
+            Expr::Assign(expr) => {
+                self.visit_expr(&expr.value)?;
+                self.assigned(&expr.name);
+                Ok(())
+            }
+            Expr::AssignList(expr) => {
+                self.visit_expr(&expr.value)?;
+                for target in &expr.targets {
+                    match &target.expr {
+                        Expr::Variable(variable) => self.assigned(&variable.name),
+                        _ => self.visit_expr(target)?,
+                    }
+                }
+                Ok(())
+            }
+            Expr::Get(expr) => self.visit_expr(&expr.object),
+            Expr::Set(expr) => {
+                self.visit_expr(&expr.value)?;
+                self.visit_expr(&expr.object)
+            }
+            Expr::List(expr) => {
+                for element in &expr.elements {
+                    self.visit_expr(element)?;
+                }
+                Ok(())
+            }
+            Expr::Index(expr) => {
+                self.visit_expr(&expr.object)?;
+                self.visit_expr(&expr.index)
+            }
+            Expr::SetIndex(expr) => {
+                self.visit_expr(&expr.value)?;
+                self.visit_expr(&expr.object)?;
+                self.visit_expr(&expr.index)
+            }
+            Expr::Update(expr) => {
+                if let Some(ref value) = expr.value {
+                    self.visit_expr(value)?;
+                }
+                // The old value is read too, so an update still counts as a use.
+                if let Expr::Variable(ref target) = expr.target.expr {
+                    self.targets.push(target.name.clone());
+                }
+                self.visit_expr(&expr.target)
+            }
+            Expr::Conditional(expr) => {
+                self.visit_expr(&expr.condition)?;
+                self.visit_expr(&expr.then_branch)?;
+                self.visit_expr(&expr.else_branch)
+            }
+            Expr::This(_) | Expr::Super(_) => {
+                self.uses_this();
+                Ok(())
+            }
+        }
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) -> Result<(), String> {
+        match stmt {
+            Stmt::Expression(stmt) => self.visit_expr(&stmt.expression),
+            Stmt::Print(stmt) => self.visit_expr(&stmt.expression),
+            Stmt::Var(stmt) => {
+                if let Some(ref initializer) = stmt.initializer {
+                    self.visit_expr(initializer)?;
+                }
+                self.variables.push(stmt.name.clone());
+                Ok(())
+            }
+            Stmt::Destructure(stmt) => {
+                self.visit_expr(&stmt.initializer)?;
+                self.variables.extend(stmt.names.iter().cloned());
+                Ok(())
+            }
+            Stmt::Block(stmt) => {
+                self.block(&stmt.statements);
+                Ok(())
+            }
+            Stmt::If(stmt) => {
+                self.visit_expr(&stmt.condition)?;
+                self.visit_stmt(&stmt.then_branch)?;
+                if let Some(ref else_branch) = stmt.else_branch {
+                    self.visit_stmt(else_branch)?;
+                }
+                Ok(())
+            }
+            Stmt::While(stmt) => {
+                self.visit_expr(&stmt.condition)?;
+                self.visit_stmt(&stmt.body)
+            }
+            Stmt::Function(stmt) => {
+                self.function(stmt, false);
+                Ok(())
+            }
+            Stmt::Return(stmt) => match stmt.value {
+                Some(ref value) => self.visit_expr(value),
+                None => Ok(()),
+            },
+            Stmt::Class(stmt) => {
+                if let Some(ref superclass) = stmt.superclass {
+                    self.visit_expr(superclass)?;
+                }
+                for hash_expr in &stmt.traits {
+                    self.visit_expr(hash_expr)?;
+                }
+                for method in &stmt.methods {
+                    self.function(method, true);
+                }
+                Ok(())
+            }
+            Stmt::Trait(stmt) => {
+                for method in &stmt.methods {
+                    self.function(method, true);
+                }
+                Ok(())
+            }
+            Stmt::Throw(stmt) => self.visit_expr(&stmt.value),
+            Stmt::Try(stmt) => {
+                self.block(&stmt.body);
+                if let Some(ref handler) = stmt.handler {
+                    self.block(&handler.body);
+                }
+                if let Some(ref finally) = stmt.finally {
+                    self.block(finally);
+                }
+                Ok(())
+            }
+            Stmt::Import(_) => Ok(()),
+            Stmt::Test(stmt) => {
+                self.block(&stmt.body);
+                Ok(())
+            }
+        }
+    }
+}
@@ -1,46 +1,244 @@
-use crate::{interpreter::interpret, parser::parse, scanner::scan_tokens};
+use crate::{
+    ast_json,
+    interpreter::{Hook, Interpreter},
+    jlox,
+    json::Json,
+    object::Object,
+    parser::parse,
+    resolver::Resolver,
+    scanner::{scan_tokens, scan_tokens_strict, CompileError},
+};
 use std::{
+    cell::RefCell,
+    collections::HashMap,
+    env, fmt,
     fs::File,
     io::{self, Read, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
 };
 
-fn skip_out(code: &str) -> &str {
+/// Drop the expected output section the test scripts end with.
+pub fn skip_out(code: &str) -> &str {
     let mut tmp: Vec<&str> = code.split("------ output ------").collect();
     tmp = tmp[0].split("------ error ------").collect();
     tmp[0]
 }
 
-pub fn run_file(path: &str) {
-    let mut file = File::open(&path).unwrap();
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoxError {
+    /// Reported by the scanner, parser or resolver, before anything runs.
+    Compile(String),
+    Runtime(String),
+}
 
-    let mut code = String::new();
-    file.read_to_string(&mut code).unwrap();
+impl LoxError {
+    /// Exit codes follow jlox, which borrows them from BSD `sysexits.h`.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            LoxError::Compile(_) => 65,
+            LoxError::Runtime(_) => 70,
+        }
+    }
+}
 
-    run_code(skip_out(&code));
+impl fmt::Display for LoxError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoxError::Compile(message) | LoxError::Runtime(message) => write!(fmt, "{}", message),
+        }
+    }
 }
 
-pub fn run_repl() {
-    let mut code = String::new();
+impl From<CompileError> for LoxError {
+    fn from(error: CompileError) -> LoxError {
+        LoxError::Compile(error.to_string())
+    }
+}
+
+/// Embedding entry point, carrying the configuration shared by every run.
+#[derive(Debug, Clone)]
+pub struct Lox {
+    search_paths: Vec<PathBuf>,
+    args: Vec<String>,
+    resolve: bool,
+    strict: bool,
+}
+
+impl Default for Lox {
+    fn default() -> Lox {
+        Lox::new()
+    }
+}
+
+impl Lox {
+    pub fn new() -> Lox {
+        Lox {
+            search_paths: Vec::new(),
+            args: Vec::new(),
+            resolve: true,
+            strict: false,
+        }
+    }
+
+    /// Add a directory `import` looks in when a module isn't found next to the importing file.
+    pub fn search_path(mut self, path: impl Into<PathBuf>) -> Lox {
+        self.search_paths.push(path.into());
+        self
+    }
+
+    /// Add every directory listed in the `LOX_PATH` environment variable.
+    pub fn search_paths_from_env(mut self) -> Lox {
+        if let Some(paths) = env::var_os("LOX_PATH") {
+            self.search_paths.extend(env::split_paths(&paths));
+        }
+        self
+    }
+
+    /// Arguments exposed to the script as the `args` global list.
+    pub fn args(mut self, args: Vec<String>) -> Lox {
+        self.args = args;
+        self
+    }
+
+    /// Skip the resolver, so variables are looked up dynamically through the environments.
+    pub fn no_resolve(mut self) -> Lox {
+        self.resolve = false;
+        self
+    }
+
+    /// Match jlox, the reference implementation, in truthiness, printing, error messages and
+    /// the words reserved, so the craftinginterpreters test suite passes.
+    pub fn strict(mut self) -> Lox {
+        self.strict = true;
+        self
+    }
+
+    pub fn interpreter(&self) -> Interpreter {
+        let mut interpreter = Interpreter::new(Rc::new(RefCell::new(HashMap::new())));
+        interpreter.set_search_paths(self.search_paths.clone());
+        if !self.resolve {
+            interpreter.disable_resolution();
+        }
+        interpreter.set_strict(self.strict);
+
+        let args = self.args.iter().cloned().map(Object::String).collect();
+        interpreter.define_global("args", Object::List(Rc::new(RefCell::new(args))));
 
-    loop {
-        code.clear();
+        interpreter
+    }
+
+    /// Run `code` in a fresh interpreter. `path` is the file it was read from, if any.
+    pub fn run_source(&self, code: &str, path: Option<&Path>) -> Result<(), LoxError> {
+        let mut interpreter = self.interpreter();
+        self.run_with(&mut interpreter, code, path)
+    }
+
+    /// Run `code` in a fresh interpreter with `hook` watching it, e.g. a debugger.
+    pub fn run_hooked(
+        &self,
+        code: &str,
+        path: Option<&Path>,
+        hook: Rc<RefCell<dyn Hook>>,
+    ) -> Result<(), LoxError> {
+        let mut interpreter = self.interpreter();
+        interpreter.set_hook(hook);
+        self.run_with(&mut interpreter, code, path)
+    }
+
+    /// Run `code` in an existing interpreter, keeping the globals of earlier runs.
+    pub fn run_with(
+        &self,
+        interpreter: &mut Interpreter,
+        code: &str,
+        path: Option<&Path>,
+    ) -> Result<(), LoxError> {
+        let compile_error = |err: CompileError| match self.strict {
+            true => LoxError::Compile(jlox::compile_error(&err)),
+            false => LoxError::from(err),
+        };
+
+        let tokens = match self.strict {
+            true => scan_tokens_strict(code),
+            false => scan_tokens(code),
+        };
+        let statements = parse(tokens.map_err(compile_error)?).map_err(compile_error)?;
+
+        if self.resolve {
+            let mut resolver = Resolver::new();
+            if self.strict {
+                resolver = resolver.strict();
+            }
+            let locals = resolver.resolve(&statements).map_err(compile_error)?;
+            interpreter.add_locals(locals);
+        }
+
+        interpreter
+            .run(&statements, path)
+            .map_err(LoxError::Runtime)
+    }
+
+    /// The syntax tree of `code` as JSON, with scope distances unless resolution is off.
+    pub fn ast_json(&self, code: &str) -> Result<Json, LoxError> {
+        let statements = parse(scan_tokens(code)?)?;
+
+        if !self.resolve {
+            return Ok(ast_json::to_json(&statements, None));
+        }
+
+        let locals = Resolver::new().resolve(&statements)?;
+        let locals = locals.borrow();
+        Ok(ast_json::to_json(&statements, Some(&locals)))
+    }
 
-        print!(">>> ");
-        io::stdout().flush().unwrap();
+    pub fn run_file(&self, path: &str) {
+        let mut file = File::open(&path).unwrap();
 
-        io::stdin().read_line(&mut code).unwrap();
-        let input = code.trim();
+        let mut code = String::new();
+        file.read_to_string(&mut code).unwrap();
 
-        run_code(input);
+        if let Err(err) = self.run_source(skip_out(&code), Some(Path::new(path))) {
+            panic!("{}", err);
+        }
+    }
+
+    pub fn run_code(&self, code: &str) {
+        if let Err(err) = self.run_source(code, None) {
+            panic!("{}", err);
+        }
+    }
+
+    pub fn run_repl(&self) {
+        let mut interpreter = self.interpreter();
+        let mut code = String::new();
+
+        loop {
+            code.clear();
+
+            print!(">>> ");
+            io::stdout().flush().unwrap();
+
+            if io::stdin().read_line(&mut code).unwrap() == 0 {
+                println!();
+                break;
+            }
+            let input = code.trim();
+
+            if let Err(err) = self.run_with(&mut interpreter, input, None) {
+                eprintln!("{}", err);
+            }
+        }
     }
 }
 
-pub fn run_code(code: &str) {
-    let tokens = scan_tokens(code);
-    // println!("{:?}", tokens);
+pub fn run_file(path: &str) {
+    Lox::new().run_file(path);
+}
 
-    let statements = parse(tokens);
-    // println!("{:?}", statements);
+pub fn run_repl() {
+    Lox::new().run_repl();
+}
 
-    interpret(&statements);
+pub fn run_code(code: &str) {
+    Lox::new().run_code(code);
 }
@@ -0,0 +1,426 @@
+//! A language server speaking LSP over stdio, for editors to show diagnostics and navigate
+//! Lox code. Documents are synced in full and re-analyzed on every request.
+
+use crate::{
+    interpreter::Interpreter,
+    json::Json,
+    linter::{self, Severity},
+    scanner::keywords,
+    symbols::{self, SymbolIndex, SymbolKind},
+    token::Token,
+};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+/// Serve requests from `input` until the client sends `exit`. Returns the exit code: 0 when
+/// `shutdown` came first, 1 otherwise.
+pub fn serve(input: &mut impl BufRead, output: &mut impl Write) -> io::Result<i32> {
+    let mut server = Server {
+        documents: HashMap::new(),
+        shutdown: false,
+        output,
+    };
+
+    while let Some(message) = read_message(input)? {
+        let method = message.get("method").and_then(Json::as_str).unwrap_or("");
+        if method == "exit" {
+            return Ok(if server.shutdown { 0 } else { 1 });
+        }
+        server.handle(method, &message)?;
+    }
+
+    Ok(1)
+}
+
+pub(crate) fn read_message(input: &mut impl BufRead) -> io::Result<Option<Json>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let Some(length) = length else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Missing Content-Length header.",
+        ));
+    };
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+
+    let body = String::from_utf8_lossy(&body);
+    Json::parse(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub(crate) fn write_message(output: &mut impl Write, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+/// An LSP position is 0-based, a token's line and column are 1-based.
+fn position(line: usize, column: usize) -> Json {
+    Json::object(vec![
+        ("line", Json::Number(line.saturating_sub(1) as f64)),
+        ("character", Json::Number(column.saturating_sub(1) as f64)),
+    ])
+}
+
+fn range(line: usize, column: usize, length: usize) -> Json {
+    Json::object(vec![
+        ("start", position(line, column)),
+        ("end", position(line, column + length)),
+    ])
+}
+
+fn token_range(token: &Token) -> Json {
+    range(token.line, token.column, token.lexeme.chars().count())
+}
+
+fn location(uri: &str, token: &Token) -> Json {
+    Json::object(vec![
+        ("uri", Json::string(uri)),
+        ("range", token_range(token)),
+    ])
+}
+
+// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#symbolKind
+fn symbol_kind(kind: SymbolKind) -> f64 {
+    match kind {
+        SymbolKind::Import => 2.0,
+        SymbolKind::Class => 5.0,
+        SymbolKind::Method => 6.0,
+        SymbolKind::Trait => 11.0,
+        SymbolKind::Function => 12.0,
+        SymbolKind::Variable | SymbolKind::Parameter => 13.0,
+        SymbolKind::Constant => 14.0,
+    }
+}
+
+fn completion_kind(kind: SymbolKind) -> f64 {
+    match kind {
+        SymbolKind::Method => 2.0,
+        SymbolKind::Function => 3.0,
+        SymbolKind::Variable | SymbolKind::Parameter => 6.0,
+        SymbolKind::Class => 7.0,
+        SymbolKind::Trait => 8.0,
+        SymbolKind::Import => 9.0,
+        SymbolKind::Constant => 21.0,
+    }
+}
+
+struct Server<'a, W: Write> {
+    documents: HashMap<String, String>,
+    shutdown: bool,
+    output: &'a mut W,
+}
+
+impl<W: Write> Server<'_, W> {
+    fn handle(&mut self, method: &str, message: &Json) -> io::Result<()> {
+        let params = message.get("params").cloned().unwrap_or(Json::Null);
+        let uri = params
+            .get("textDocument")
+            .and_then(|x| x.get("uri"))
+            .and_then(Json::as_str)
+            .unwrap_or("")
+            .to_string();
+
+        let result = match method {
+            "initialize" => Some(self.initialize()),
+            "shutdown" => {
+                self.shutdown = true;
+                Some(Json::Null)
+            }
+            "textDocument/didOpen" => {
+                let text = params.get("textDocument").and_then(|x| x.get("text"));
+                self.update(&uri, text)?;
+                None
+            }
+            "textDocument/didChange" => {
+                // Only full syncs are advertised, so the last change is the whole text.
+                let changes = params.get("contentChanges").and_then(Json::as_array);
+                let text = changes.and_then(|x| x.last()).and_then(|x| x.get("text"));
+                self.update(&uri, text)?;
+                None
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                self.publish(&uri, Vec::new())?;
+                None
+            }
+            "textDocument/definition" => Some(self.definition(&uri, &params)),
+            "textDocument/references" => Some(self.references(&uri, &params)),
+            "textDocument/hover" => Some(self.hover(&uri, &params)),
+            "textDocument/documentSymbol" => Some(self.document_symbols(&uri)),
+            "textDocument/completion" => Some(self.completion(&uri)),
+            _ => None,
+        };
+
+        // Notifications have no id and get no response.
+        let Some(id) = message.get("id").cloned() else {
+            return Ok(());
+        };
+        let response = match result {
+            Some(result) => Json::object(vec![
+                ("jsonrpc", Json::string("2.0")),
+                ("id", id),
+                ("result", result),
+            ]),
+            None => Json::object(vec![
+                ("jsonrpc", Json::string("2.0")),
+                ("id", id),
+                (
+                    "error",
+                    Json::object(vec![
+                        ("code", Json::Number(-32601.0)),
+                        (
+                            "message",
+                            Json::string(format!("Unknown method `{}`.", method)),
+                        ),
+                    ]),
+                ),
+            ]),
+        };
+        write_message(self.output, &response)
+    }
+
+    fn initialize(&self) -> Json {
+        Json::object(vec![
+            (
+                "capabilities",
+                Json::object(vec![
+                    // Full document sync.
+                    ("textDocumentSync", Json::Number(1.0)),
+                    ("definitionProvider", Json::Bool(true)),
+                    ("referencesProvider", Json::Bool(true)),
+                    ("hoverProvider", Json::Bool(true)),
+                    ("documentSymbolProvider", Json::Bool(true)),
+                    ("completionProvider", Json::object(vec![])),
+                ]),
+            ),
+            (
+                "serverInfo",
+                Json::object(vec![("name", Json::string("rlox"))]),
+            ),
+        ])
+    }
+
+    fn update(&mut self, uri: &str, text: Option<&Json>) -> io::Result<()> {
+        let Some(text) = text.and_then(Json::as_str) else {
+            return Ok(());
+        };
+        self.documents.insert(uri.to_string(), text.to_string());
+        let diagnostics = diagnostics(text);
+        self.publish(uri, diagnostics)
+    }
+
+    fn publish(&mut self, uri: &str, diagnostics: Vec<Json>) -> io::Result<()> {
+        let notification = Json::object(vec![
+            ("jsonrpc", Json::string("2.0")),
+            ("method", Json::string("textDocument/publishDiagnostics")),
+            (
+                "params",
+                Json::object(vec![
+                    ("uri", Json::string(uri)),
+                    ("diagnostics", Json::Array(diagnostics)),
+                ]),
+            ),
+        ]);
+        write_message(self.output, &notification)
+    }
+
+    fn index(&self, uri: &str) -> Option<SymbolIndex> {
+        self.documents.get(uri).and_then(|x| symbols::index(x).ok())
+    }
+
+    // The index of the document and the symbol under the cursor.
+    fn symbol_at(&self, uri: &str, params: &Json) -> Option<(SymbolIndex, usize)> {
+        let index = self.index(uri)?;
+        let position = params.get("position")?;
+        let line = position.get("line")?.as_f64()? as usize + 1;
+        let column = position.get("character")?.as_f64()? as usize + 1;
+        let symbol = index.symbol_at(line, column)?;
+        Some((index, symbol))
+    }
+
+    fn definition(&self, uri: &str, params: &Json) -> Json {
+        match self.symbol_at(uri, params) {
+            Some((index, symbol)) => location(uri, &index.symbols[symbol].name),
+            None => Json::Null,
+        }
+    }
+
+    fn references(&self, uri: &str, params: &Json) -> Json {
+        let Some((index, symbol)) = self.symbol_at(uri, params) else {
+            return Json::Null;
+        };
+
+        let include_declaration = params
+            .get("context")
+            .and_then(|x| x.get("includeDeclaration"))
+            == Some(&Json::Bool(true));
+        let mut tokens = index.references_to(symbol);
+        if include_declaration {
+            tokens.insert(0, index.symbols[symbol].name.clone());
+        }
+
+        Json::Array(tokens.iter().map(|x| location(uri, x)).collect())
+    }
+
+    fn hover(&self, uri: &str, params: &Json) -> Json {
+        let Some((index, symbol)) = self.symbol_at(uri, params) else {
+            return Json::Null;
+        };
+
+        let symbol = &index.symbols[symbol];
+        let value = format!(
+            "```lox\n{}\n```\nDeclared on line {}.",
+            symbol.detail, symbol.name.line
+        );
+        Json::object(vec![(
+            "contents",
+            Json::object(vec![
+                ("kind", Json::string("markdown")),
+                ("value", Json::string(value)),
+            ]),
+        )])
+    }
+
+    fn document_symbols(&self, uri: &str) -> Json {
+        let Some(index) = self.index(uri) else {
+            return Json::Array(Vec::new());
+        };
+
+        // Functions, classes, traits and methods nest under the declaration they are in.
+        fn children(index: &SymbolIndex, parent: Option<usize>) -> Vec<Json> {
+            index
+                .symbols
+                .iter()
+                .enumerate()
+                .filter(|(_, x)| x.parent == parent)
+                .filter(|(_, x)| {
+                    matches!(
+                        x.kind,
+                        SymbolKind::Function
+                            | SymbolKind::Method
+                            | SymbolKind::Class
+                            | SymbolKind::Trait
+                    )
+                })
+                .map(|(i, x)| {
+                    Json::object(vec![
+                        ("name", Json::string(x.name.lexeme.clone())),
+                        ("detail", Json::string(x.detail.clone())),
+                        ("kind", Json::Number(symbol_kind(x.kind))),
+                        ("range", token_range(&x.name)),
+                        ("selectionRange", token_range(&x.name)),
+                        ("children", Json::Array(children(index, Some(i)))),
+                    ])
+                })
+                .collect()
+        }
+
+        Json::Array(children(&index, None))
+    }
+
+    fn completion(&self, uri: &str) -> Json {
+        let mut items = Vec::new();
+        let mut seen = Vec::new();
+        let mut add = |label: &str, kind: f64, detail: &str| {
+            if !seen.contains(&label.to_string()) {
+                seen.push(label.to_string());
+                items.push(Json::object(vec![
+                    ("label", Json::string(label)),
+                    ("kind", Json::Number(kind)),
+                    ("detail", Json::string(detail)),
+                ]));
+            }
+        };
+
+        if let Some(index) = self.index(uri) {
+            for symbol in &index.symbols {
+                if symbol.kind != SymbolKind::Method {
+                    let kind = completion_kind(symbol.kind);
+                    add(&symbol.name.lexeme, kind, &symbol.detail);
+                }
+            }
+        }
+        for name in Interpreter::builtin_names() {
+            add(&name, 3.0, "builtin");
+        }
+        for keyword in keywords() {
+            add(keyword, 14.0, "keyword");
+        }
+
+        Json::Array(items)
+    }
+}
+
+/// Compile errors, or lints when the code compiles, as LSP diagnostics.
+fn diagnostics(text: &str) -> Vec<Json> {
+    let lines: Vec<&str> = text.lines().collect();
+    let line_length = |line: usize| {
+        lines
+            .get(line.wrapping_sub(1))
+            .map_or(0, |x| x.chars().count())
+    };
+
+    let diagnostic = |range: Json, severity: f64, code: Option<&str>, message: &str| {
+        let mut fields = vec![
+            ("range", range),
+            ("severity", Json::Number(severity)),
+            ("source", Json::string("rlox")),
+            ("message", Json::string(message)),
+        ];
+        fields.extend(code.map(|x| ("code", Json::string(x))));
+        Json::object(fields)
+    };
+
+    match linter::lint(text) {
+        Ok(lints) => lints
+            .iter()
+            .map(|x| {
+                // Lints point at the start of a name; underline the whole word.
+                let rest: String = lines
+                    .get(x.line - 1)
+                    .map(|line| line.chars().skip(x.column - 1).collect())
+                    .unwrap_or_default();
+                let length = rest
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || *c == '_')
+                    .count()
+                    .max(1);
+                let severity = match x.lint.severity {
+                    Severity::Error => 1.0,
+                    Severity::Warning => 2.0,
+                    Severity::Info => 3.0,
+                };
+                let range = range(x.line, x.column, length);
+                diagnostic(range, severity, Some(x.lint.code), &x.message)
+            })
+            .collect(),
+        Err(error) => {
+            // Errors of the scanner only know their line, so the whole line is marked.
+            let range = match error.token {
+                Some(ref token) => token_range(token),
+                None => range(error.line, 1, line_length(error.line)),
+            };
+            vec![diagnostic(range, 1.0, None, &error.message)]
+        }
+    }
+}
@@ -1,17 +1,445 @@
-use rlox::lox;
-use std::{env, process};
+use rlox::{
+    coverage::{Coverage, Recorder},
+    dap,
+    debugger::Debugger,
+    formatter,
+    linter::{self, Severity},
+    lox::{skip_out, Lox, LoxError},
+    lsp,
+    parser::parse,
+    profiler::Profiler,
+    scanner::scan_tokens,
+    testing,
+};
+use std::{
+    cell::RefCell,
+    env, fs,
+    io::{self, Read},
+    path::Path,
+    process,
+    rc::Rc,
+};
+
+const USAGE: &str = "Usage: rlox [options] [run] [script | -e code | -] [args...]
+       rlox fmt [--check] [file... | -]
+       rlox lint [file... | -]
+       rlox test [dir | file...]
+       rlox lsp
+       rlox debug script [args...]
+       rlox dap
+
+Options:
+  -e CODE          Run CODE instead of a script file
+  -                Read the script from stdin
+  --dump-tokens    Print the tokens of the script instead of running it
+  --dump-ast       Print the syntax tree of the script instead of running it
+  --dump-json      Print the syntax tree and resolved scopes as JSON instead of running it
+  --no-resolve     Skip variable resolution, looking variables up dynamically
+  --strict         Behave like jlox: its truthiness, number printing, error messages and
+                   reserved words, with rlox's extensions to the language turned off
+  --profile        Report calls and time per function and hits per line on stderr
+  --profile-stacks FILE
+                   Also write collapsed call stacks to FILE, for flamegraph tools
+  --coverage FILE  Record the lines run into the lcov report FILE, merging with its contents
+  -h, --help       Print this help
+
+Without a script, an interactive prompt is started.
+`fmt` rewrites files in the canonical style, or with --check lists the files it would change.
+`lint` reports likely mistakes, and fails when any of them is a warning or an error.
+`test` runs the `test \"name\" { ... }` blocks and `fun test_name()` functions of the .lox
+files given, or found under the current directory, each in a fresh interpreter.
+`debug` runs a script under a step debugger, type `help` at its prompt for commands.
+`lsp` and `dap` serve the Language Server and Debug Adapter Protocols over stdio, for editors.
+Modules are also searched for in the directories listed in LOX_PATH.";
+
+enum Source {
+    File(String),
+    Code(String),
+    Stdin,
+}
+
+#[derive(PartialEq)]
+enum Dump {
+    None,
+    Tokens,
+    Ast,
+    Json,
+}
+
+struct Options {
+    source: Option<Source>,
+    args: Vec<String>,
+    dump: Dump,
+    resolve: bool,
+    strict: bool,
+    profile: bool,
+    profile_stacks: Option<String>,
+    coverage: Option<String>,
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    process::exit(64);
+}
+
+fn parse_args(args: Vec<String>) -> Options {
+    let mut options = Options {
+        source: None,
+        args: Vec::new(),
+        dump: Dump::None,
+        resolve: true,
+        strict: false,
+        profile: false,
+        profile_stacks: None,
+        coverage: None,
+    };
+
+    let mut args = args.into_iter().skip(1).peekable();
+    if args.peek().map(|x| x.as_str()) == Some("run") {
+        args.next();
+        if args.peek().is_none() {
+            usage_error("Missing script after `run`.");
+        }
+    }
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "--dump-tokens" => options.dump = Dump::Tokens,
+            "--dump-ast" => options.dump = Dump::Ast,
+            "--dump-json" => options.dump = Dump::Json,
+            "--no-resolve" => options.resolve = false,
+            "--strict" => options.strict = true,
+            "--profile" => options.profile = true,
+            "--profile-stacks" => match args.next() {
+                Some(file) => {
+                    options.profile = true;
+                    options.profile_stacks = Some(file);
+                }
+                None => usage_error("Missing file after `--profile-stacks`."),
+            },
+            "--coverage" => match args.next() {
+                Some(file) => options.coverage = Some(file),
+                None => usage_error("Missing file after `--coverage`."),
+            },
+            "-e" => match args.next() {
+                Some(code) => options.source = Some(Source::Code(code)),
+                None => usage_error("Missing code after `-e`."),
+            },
+            "-" => options.source = Some(Source::Stdin),
+            _ if arg.starts_with('-') => usage_error(&format!("Unknown option `{}`.", arg)),
+            _ => options.source = Some(Source::File(arg)),
+        }
+
+        // Everything after the script belongs to the script.
+        if options.source.is_some() {
+            options.args.extend(args);
+            break;
+        }
+    }
+
+    options
+}
+
+fn read_source(source: &Source) -> String {
+    let result = match source {
+        Source::File(path) => fs::read_to_string(path),
+        Source::Code(code) => Ok(code.clone()),
+        Source::Stdin => {
+            let mut code = String::new();
+            io::stdin().read_to_string(&mut code).map(|_| code)
+        }
+    };
+
+    result.unwrap_or_else(|err| {
+        eprintln!("Can't read script: {}.", err);
+        process::exit(66);
+    })
+}
+
+fn dump(lox: &Lox, code: &str, dump: &Dump) -> Result<(), LoxError> {
+    if *dump == Dump::Json {
+        println!("{}", lox.ast_json(code)?.pretty());
+        return Ok(());
+    }
+
+    let tokens = scan_tokens(code)?;
+    if *dump == Dump::Tokens {
+        for token in tokens {
+            println!("{}", token);
+        }
+        return Ok(());
+    }
+
+    for statement in parse(tokens)? {
+        println!("{}", statement);
+    }
+    Ok(())
+}
+
+fn fmt(args: Vec<String>) {
+    let check = args.iter().any(|x| x == "--check");
+    let files: Vec<String> = args.into_iter().filter(|x| x != "--check").collect();
+    if files.is_empty() {
+        usage_error("Missing files to format.");
+    }
+
+    let mut changed = false;
+    let mut failed = false;
+    for file in files {
+        let source = match file.as_str() {
+            "-" => Source::Stdin,
+            _ => Source::File(file.clone()),
+        };
+        let code = read_source(&source);
+
+        let formatted = match formatter::format(&code) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                eprintln!("{}: {}", file, err);
+                failed = true;
+                continue;
+            }
+        };
+
+        // Stdin is always echoed, so `rlox fmt -` works as a filter.
+        if file == "-" && !check {
+            print!("{}", formatted);
+            continue;
+        }
+
+        if formatted == code {
+            continue;
+        }
+        changed = true;
+
+        if check {
+            println!("{}", file);
+        } else if let Err(err) = fs::write(&file, formatted) {
+            eprintln!("Can't write `{}`: {}.", file, err);
+            process::exit(74);
+        }
+    }
+
+    if failed {
+        process::exit(65);
+    }
+    if check && changed {
+        process::exit(1);
+    }
+}
+
+fn lint(files: Vec<String>) {
+    if files.is_empty() {
+        usage_error("Missing files to lint.");
+    }
+
+    let mut failed = false;
+    let mut found = false;
+    for file in files {
+        let source = match file.as_str() {
+            "-" => Source::Stdin,
+            _ => Source::File(file.clone()),
+        };
+
+        match linter::lint(&read_source(&source)) {
+            Ok(diagnostics) => {
+                for diagnostic in diagnostics {
+                    found |= diagnostic.lint.severity >= Severity::Warning;
+                    println!("{}: {}", file, diagnostic);
+                }
+            }
+            Err(err) => {
+                eprintln!("{}: {}", file, err);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        process::exit(65);
+    }
+    if found {
+        process::exit(1);
+    }
+}
+
+fn test(paths: Vec<String>) {
+    let paths = match paths.is_empty() {
+        true => vec![".".to_string()],
+        false => paths,
+    };
+    let lox = Lox::new().search_paths_from_env();
+
+    let (mut passed, mut failed) = (0, 0);
+    for path in paths {
+        let files = testing::files(Path::new(&path)).unwrap_or_else(|err| {
+            eprintln!("Can't read `{}`: {}.", path, err);
+            process::exit(66);
+        });
+        for file in files {
+            let results = match testing::run_file(&lox, &file) {
+                Ok(results) => results,
+                Err(err) => {
+                    println!("ERROR {}: {}", file.display(), err);
+                    failed += 1;
+                    continue;
+                }
+            };
+            for result in results {
+                match result.failure {
+                    Some(_) => failed += 1,
+                    None => passed += 1,
+                }
+                println!("{}", result);
+            }
+        }
+    }
+
+    println!("\n{} passed, {} failed.", passed, failed);
+    if failed > 0 {
+        process::exit(1);
+    }
+}
+
+fn profile(
+    lox: &Lox,
+    code: &str,
+    path: Option<&Path>,
+    stacks: Option<String>,
+) -> Result<(), LoxError> {
+    let profiler = Rc::new(RefCell::new(Profiler::new()));
+    let result = lox.run_hooked(code, path, profiler.clone());
+
+    let mut profiler = profiler.borrow_mut();
+    profiler.finish();
+    eprint!("{}", profiler.report());
+    if let Some(file) = stacks {
+        if let Err(err) = fs::write(&file, profiler.collapsed()) {
+            eprintln!("Can't write `{}`: {}.", file, err);
+        }
+    }
+
+    result
+}
+
+/// Run with line coverage, merged into the lcov report `file` if it already exists.
+fn coverage(lox: &Lox, code: &str, path: Option<&Path>, file: String) -> Result<(), LoxError> {
+    let recorder = Rc::new(RefCell::new(Recorder::new()));
+    let result = lox.run_hooked(code, path, recorder.clone());
+
+    let mut coverage = match fs::read_to_string(&file) {
+        Ok(text) => Coverage::from_lcov(&text).unwrap_or_else(|err| {
+            eprintln!("{}: {}", file, err);
+            process::exit(65);
+        }),
+        Err(_) => Coverage::new(),
+    };
+    let name = path
+        .map(|x| x.canonicalize().unwrap_or(x.to_path_buf()))
+        .map_or("-".to_string(), |x| x.to_string_lossy().to_string());
+    coverage
+        .add(&name, code, &recorder.borrow())
+        .map_err(LoxError::Compile)?;
+    if let Err(err) = fs::write(&file, coverage.to_lcov()) {
+        eprintln!("Can't write `{}`: {}.", file, err);
+    }
+
+    result
+}
+
+fn debug(mut args: Vec<String>) {
+    if args.is_empty() {
+        usage_error("Missing script to debug.");
+    }
+    let path = args.remove(0);
+    let code = read_source(&Source::File(path.clone()));
+    let code = skip_out(&code);
+
+    let debugger = Debugger::new(code, io::stdin().lock(), io::stdout());
+    let lox = Lox::new().search_paths_from_env().args(args);
+    if let Err(err) = lox.run_hooked(
+        code,
+        Some(Path::new(&path)),
+        Rc::new(RefCell::new(debugger)),
+    ) {
+        eprintln!("{}", err);
+        process::exit(err.exit_code());
+    }
+}
+
+fn dap() {
+    if let Err(err) = dap::serve(io::stdin().lock(), io::stdout()) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
+
+fn lsp() {
+    let stdin = io::stdin();
+    match lsp::serve(&mut stdin.lock(), &mut io::stdout()) {
+        Ok(code) => process::exit(code),
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    }
+}
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    let argc = args.len();
+    match args.get(1).map(|x| x.as_str()) {
+        Some("fmt") => return fmt(args.into_iter().skip(2).collect()),
+        Some("lint") => return lint(args.into_iter().skip(2).collect()),
+        Some("test") => return test(args.into_iter().skip(2).collect()),
+        Some("lsp") => return lsp(),
+        Some("dap") => return dap(),
+        Some("debug") => return debug(args.into_iter().skip(2).collect()),
+        _ => {}
+    }
+
+    let options = parse_args(args);
+
+    let mut lox = Lox::new().search_paths_from_env().args(options.args);
+    if !options.resolve {
+        lox = lox.no_resolve();
+    }
+    if options.strict {
+        lox = lox.strict();
+    }
+
+    let source = match options.source {
+        Some(source) => source,
+        None if options.dump == Dump::None => {
+            lox.run_repl();
+            return;
+        }
+        None => usage_error("Missing script to dump."),
+    };
+
+    let code = read_source(&source);
+    let (code, path) = match source {
+        Source::File(ref path) => (skip_out(&code), Some(Path::new(path))),
+        _ => (code.as_str(), None),
+    };
+    let result = match options.dump {
+        Dump::None if options.profile && options.coverage.is_some() => {
+            usage_error("`--profile` and `--coverage` can't be used together.")
+        }
+        Dump::None if options.profile => profile(&lox, code, path, options.profile_stacks),
+        Dump::None if options.coverage.is_some() => {
+            coverage(&lox, code, path, options.coverage.unwrap_or_default())
+        }
+        Dump::None => lox.run_source(code, path),
+        ref dump_mode => dump(&lox, code, dump_mode),
+    };
 
     // Check exit code by `echo $?`
-    if argc > 2 {
-        println!("Usage: lox[ script]");
-        process::exit(-1); // 255
-    } else if argc == 2 {
-        lox::run_file(&args[1]) // 0
-    } else {
-        lox::run_repl(); // 130
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(err.exit_code());
     }
 }
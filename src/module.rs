@@ -0,0 +1,54 @@
+use crate::{
+    environment::{Environment, Stateful},
+    interpreter::InterpretError,
+    object::Object,
+};
+use std::{cell::RefCell, fmt, path::PathBuf, rc::Rc};
+
+#[derive(Debug)]
+pub struct Module {
+    pub name: String,
+    pub path: PathBuf,
+    globals: Rc<RefCell<Environment>>,
+}
+
+impl Module {
+    pub fn new(name: String, path: PathBuf, globals: Rc<RefCell<Environment>>) -> Module {
+        Module {
+            name,
+            path,
+            globals,
+        }
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.globals.borrow().names()
+    }
+}
+
+impl Stateful for Module {
+    fn get(&self, key: &str) -> Result<Object, InterpretError> {
+        // Only the module's own top-level bindings are exported, not the builtins.
+        if let Some(value) = self.globals.borrow().get_own(key) {
+            return Ok(value);
+        }
+
+        Err(InterpretError::new(format!(
+            "Module `{}` has no binding `{}`.",
+            self.name, key,
+        )))
+    }
+
+    fn set(&mut self, key: &str, _: Object) -> Result<(), InterpretError> {
+        Err(InterpretError::new(format!(
+            "Can't assign `{}` of module `{}`.",
+            key, self.name,
+        )))
+    }
+}
+
+impl fmt::Display for Module {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "<module {}>", self.name)
+    }
+}
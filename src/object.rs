@@ -1,15 +1,21 @@
-use crate::{class::IsClass, function::IsFunction, instance::IsInstance};
-use std::{cell::RefCell, fmt, rc::Rc};
+use crate::{
+    class::IsClass, function::IsFunction, instance::IsInstance, module::Module, traits::Trait,
+};
+use std::{cell::RefCell, cmp::Ordering, fmt, rc::Rc};
 
 #[derive(Debug, Clone)]
 pub enum Object {
     Nil,
     Boolean(bool),
     Number(f64),
+    Integer(i64),
     String(String),
     Function(Rc<RefCell<dyn IsFunction>>),
     Instance(Rc<RefCell<dyn IsInstance>>),
     Class(Rc<RefCell<dyn IsClass>>),
+    Trait(Rc<Trait>),
+    List(Rc<RefCell<Vec<Object>>>),
+    Module(Rc<RefCell<Module>>),
 }
 
 impl Object {
@@ -18,10 +24,55 @@ impl Object {
             Object::Nil => false,
             Object::Boolean(false) => false,
             Object::Number(n) => *n != 0.0,
+            Object::Integer(n) => *n != 0,
             Object::String(s) => s.len() > 0,
+            Object::List(l) => !l.borrow().is_empty(),
             _ => true,
         }
     }
+
+    /// The value of a number as a float, integers included.
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Object::Number(n) => Some(*n),
+            Object::Integer(n) => Some(*n as f64),
+            _ => None,
+        }
+    }
+
+    /// How two numbers order, exactly even when an integer meets a float. `None` for other
+    /// values, and when either is NaN.
+    pub fn compare(&self, other: &Object) -> Option<Ordering> {
+        match (self, other) {
+            (Object::Integer(a), Object::Integer(b)) => Some(a.cmp(b)),
+            (Object::Number(a), Object::Number(b)) => a.partial_cmp(b),
+            (Object::Integer(a), Object::Number(b)) => compare_mixed(*a, *b),
+            (Object::Number(a), Object::Integer(b)) => compare_mixed(*b, *a).map(Ordering::reverse),
+            _ => None,
+        }
+    }
+}
+
+/// Compare without converting `a` to a float, which would round integers above 2^53.
+fn compare_mixed(a: i64, b: f64) -> Option<Ordering> {
+    // 2^63, the first float above every i64.
+    const LIMIT: f64 = 9_223_372_036_854_775_808.0;
+    if b.is_nan() {
+        return None;
+    }
+    if b >= LIMIT {
+        return Some(Ordering::Less);
+    }
+    if b < -LIMIT {
+        return Some(Ordering::Greater);
+    }
+
+    // In range, the integral part of `b` converts exactly; its fraction breaks a tie.
+    let whole = b.trunc();
+    match a.cmp(&(whole as i64)) {
+        Ordering::Equal => 0.0.partial_cmp(&(b - whole)),
+        ordering => Some(ordering),
+    }
 }
 
 impl fmt::Display for Object {
@@ -30,10 +81,17 @@ impl fmt::Display for Object {
             Object::Nil => write!(fmt, "nil"),
             Object::Boolean(b) => write!(fmt, "{}", b),
             Object::Number(n) => write!(fmt, "{}", n),
+            Object::Integer(n) => write!(fmt, "{}", n),
             Object::String(s) => write!(fmt, "{}", s),
             Object::Function(f) => write!(fmt, "{}", f.borrow()),
             Object::Instance(i) => write!(fmt, "{}", i.borrow()),
             Object::Class(c) => write!(fmt, "{}", c.borrow()),
+            Object::Trait(t) => write!(fmt, "{}", t),
+            Object::Module(m) => write!(fmt, "{}", m.borrow()),
+            Object::List(l) => {
+                let items: Vec<String> = l.borrow().iter().map(|x| x.to_string()).collect();
+                write!(fmt, "[{}]", items.join(", "))
+            }
         }
     }
 }
@@ -44,10 +102,17 @@ impl PartialEq for Object {
             (Object::Nil, Object::Nil) => true,
             (Object::Boolean(a), Object::Boolean(b)) => a == b,
             (Object::Number(a), Object::Number(b)) => a == b,
+            (Object::Integer(a), Object::Integer(b)) => a == b,
+            (Object::Integer(a), Object::Number(b)) | (Object::Number(b), Object::Integer(a)) => {
+                compare_mixed(*a, *b) == Some(Ordering::Equal)
+            }
             (Object::String(a), Object::String(b)) => a == b,
             (Object::Function(a), Object::Function(b)) => Rc::ptr_eq(a, b),
             (Object::Instance(a), Object::Instance(b)) => Rc::ptr_eq(a, b),
             (Object::Class(a), Object::Class(b)) => Rc::ptr_eq(a, b),
+            (Object::Trait(a), Object::Trait(b)) => Rc::ptr_eq(a, b),
+            (Object::List(a), Object::List(b)) => Rc::ptr_eq(a, b),
+            (Object::Module(a), Object::Module(b)) => Rc::ptr_eq(a, b),
             _ => false,
         }
     }
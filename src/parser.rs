@@ -1,30 +1,33 @@
 use crate::{
     expr::{
-        AssignExpr, BinaryExpr, CallExpr, Expr, GetExpr, GroupingExpr, HashExpr, LiteralExpr,
-        LogicalExpr, SetExpr, SuperExpr, ThisExpr, UnaryExpr, VariableExpr,
+        AssignExpr, AssignListExpr, BinaryExpr, CallExpr, ConditionalExpr, Expr, GetExpr,
+        GroupingExpr, HashExpr, IndexExpr, ListExpr, LiteralExpr, LogicalExpr, SetExpr,
+        SetIndexExpr, SuperExpr, ThisExpr, UnaryExpr, UpdateExpr, VariableExpr,
     },
     object::Object,
+    scanner::CompileError,
     stmt::{
-        BlockStmt, ClassStmt, ExpressionStmt, FunctionStmt, IfStmt, PrintStmt, ReturnStmt, Stmt,
-        VarStmt, WhileStmt,
+        BlockStmt, CatchClause, ClassStmt, DestructureStmt, ExpressionStmt, FunctionStmt, IfStmt,
+        ImportStmt, PrintStmt, ReturnStmt, Stmt, TestStmt, ThrowStmt, TraitStmt, TryStmt, VarStmt,
+        WhileStmt,
     },
     token::{Token, TokenType},
 };
-use std::rc::Rc;
+use std::{path::Path, rc::Rc};
 
-pub fn parse(tokens: Vec<Rc<Token>>) -> Vec<Rc<Stmt>> {
+pub fn parse(tokens: Vec<Rc<Token>>) -> Result<Vec<Rc<Stmt>>, ParseError> {
     let mut parser = Parser::new(tokens);
     let mut statements: Vec<Rc<Stmt>> = Vec::new();
     while !parser.is_at_end() {
         match parser.declaration() {
             Ok(stmt) => statements.push(stmt),
-            Err(err) => panic!("{}", err),
+            Err(err) => return Err(err),
         }
     }
-    statements
+    Ok(statements)
 }
 
-type ParseError = String;
+pub type ParseError = CompileError;
 
 struct Parser {
     tokens: Vec<Rc<Token>>,
@@ -75,12 +78,17 @@ impl Parser {
         false
     }
 
+    fn check_word(&self, word: &str) -> bool {
+        self.peek()
+            .is_some_and(|x| x.token_type == TokenType::Identifier && x.lexeme == word)
+    }
+
     fn consume(&mut self, token_type: &TokenType, message: &str) -> Result<Rc<Token>, ParseError> {
         let token = self.peek().unwrap();
 
         match self.check(token_type) {
             true => Ok(self.advance()),
-            false => Err(format!("[line {}] <{:?}> : {}", token.line, token, message)),
+            false => Err(CompileError::at(&token, message)),
         }
     }
 
@@ -88,15 +96,39 @@ impl Parser {
         if self.find(&[TokenType::Class]) {
             return self.class_declaration();
         }
-        if self.find(&[TokenType::Var]) {
+        if self.find(&[TokenType::Trait]) {
+            return self.trait_declaration();
+        }
+        if self.find(&[TokenType::Import]) {
+            return self.import_declaration();
+        }
+        if self.find(&[TokenType::Var, TokenType::Let, TokenType::Const]) {
             return self.var_declaration();
         }
         if self.find(&[TokenType::Fun]) {
             return Ok(Rc::new(Stmt::Function(self.function("function")?)));
         }
+        // `test` is only a keyword before a string, so it stays usable as a name.
+        if self.check_word("test")
+            && self
+                .peek_at(self.current + 1)
+                .is_some_and(|x| x.token_type == TokenType::String)
+        {
+            self.advance();
+            return self.test_declaration();
+        }
         self.statement()
     }
 
+    fn test_declaration(&mut self) -> Result<Rc<Stmt>, ParseError> {
+        let keyword = self.previous();
+        let name = self.consume(&TokenType::String, "Expect test name.")?;
+        self.consume(&TokenType::LeftBrace, "Expect '{' before test body.")?;
+        let body = self.block()?;
+
+        Ok(Rc::new(Stmt::Test(TestStmt::new(keyword, name, body))))
+    }
+
     fn function(&mut self, kind: &str) -> Result<FunctionStmt, ParseError> {
         let name = self.consume(
             &TokenType::Identifier,
@@ -113,9 +145,9 @@ impl Parser {
             loop {
                 if parameters.len() > Parser::PARAM_MAX_COUNT {
                     let token = self.previous();
-                    return Err(format!(
-                        "[line {}] <{:?}> : Can't have more than 255 arguments.",
-                        token.line, token
+                    return Err(CompileError::at(
+                        &token,
+                        "Can't have more than 255 arguments.",
                     ));
                 }
                 parameters.push(self.consume(&TokenType::Identifier, "Expect parameter name.")?);
@@ -146,6 +178,17 @@ impl Parser {
             superclass = Some(HashExpr::new(Expr::Variable(VariableExpr::new(name))));
         }
 
+        let mut traits: Vec<HashExpr> = Vec::new();
+        if self.find(&[TokenType::With]) {
+            loop {
+                let name = self.consume(&TokenType::Identifier, "Expect trait name.")?;
+                traits.push(HashExpr::new(Expr::Variable(VariableExpr::new(name))));
+                if !self.find(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+
         self.consume(&TokenType::LeftBrace, "Expect '{' before class body.")?;
 
         let mut methods: Vec<FunctionStmt> = Vec::new();
@@ -156,15 +199,114 @@ impl Parser {
         self.consume(&TokenType::RightBrace, "Expect '}' before class body.")?;
 
         Ok(Rc::new(Stmt::Class(ClassStmt::new(
-            name, superclass, methods,
+            name, superclass, traits, methods,
+        ))))
+    }
+
+    fn trait_declaration(&mut self) -> Result<Rc<Stmt>, ParseError> {
+        let name = self.consume(&TokenType::Identifier, "Expect trait name.")?;
+
+        self.consume(&TokenType::LeftBrace, "Expect '{' before trait body.")?;
+
+        let mut methods: Vec<FunctionStmt> = Vec::new();
+        while !self.check(&TokenType::RightBrace) {
+            methods.push(self.function("method")?)
+        }
+
+        self.consume(&TokenType::RightBrace, "Expect '}' after trait body.")?;
+
+        Ok(Rc::new(Stmt::Trait(TraitStmt::new(name, methods))))
+    }
+
+    fn import_declaration(&mut self) -> Result<Rc<Stmt>, ParseError> {
+        let keyword = self.previous();
+
+        let mut names: Vec<Rc<Token>> = Vec::new();
+        if self.find(&[TokenType::LeftBrace]) {
+            loop {
+                names.push(self.consume(&TokenType::Identifier, "Expect imported name.")?);
+                if !self.find(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+            self.consume(&TokenType::RightBrace, "Expect '}' after imported names.")?;
+
+            if !self.check_word("from") {
+                let token = self.peek().unwrap();
+                return Err(CompileError::at(
+                    &token,
+                    "Expect 'from' after imported names.",
+                ));
+            }
+            self.advance();
+        }
+
+        let path = self.consume(&TokenType::String, "Expect module path.")?;
+
+        let mut name: Option<Rc<Token>> = None;
+        if names.is_empty() {
+            if self.check_word("as") {
+                self.advance();
+                name =
+                    Some(self.consume(&TokenType::Identifier, "Expect module name after 'as'.")?);
+            } else {
+                name = Some(Parser::module_name(&path)?);
+            }
+        }
+
+        self.consume(&TokenType::Semicolon, "Expect ';' after import.")?;
+
+        Ok(Rc::new(Stmt::Import(ImportStmt::new(
+            keyword, path, name, names,
         ))))
     }
 
+    /// `import "lib/util.lox";` binds the module to `util`.
+    fn module_name(path: &Token) -> Result<Rc<Token>, ParseError> {
+        let stem = match path.literal {
+            Object::String(ref s) => Path::new(s)
+                .file_stem()
+                .map(|x| x.to_string_lossy().to_string()),
+            _ => None,
+        }
+        .unwrap_or_default();
+
+        let mut chars = stem.chars();
+        let is_identifier = chars
+            .next()
+            .is_some_and(|x| x.is_ascii_alphabetic() || x == '_')
+            && chars.all(|x| x.is_ascii_alphanumeric() || x == '_');
+
+        if !is_identifier {
+            return Err(CompileError::at(
+                path,
+                format!(
+                    "Module name `{}` is not an identifier, use 'as' to name it.",
+                    stem
+                ),
+            ));
+        }
+
+        Ok(Rc::new(Token::new(
+            TokenType::Identifier,
+            stem,
+            Object::Nil,
+            path.line,
+        )))
+    }
+
     fn var_declaration(&mut self) -> Result<Rc<Stmt>, ParseError> {
+        let keyword = self.previous();
+        if self.find(&[TokenType::LeftBracket, TokenType::LeftBrace]) {
+            return self.destructure_declaration(keyword);
+        }
         let name = self.consume(&TokenType::Identifier, "Expect variable name.")?;
 
         let mut initializer: Option<Rc<HashExpr>> = None;
-        if self.find(&[TokenType::Equal]) {
+        if keyword.token_type == TokenType::Const {
+            self.consume(&TokenType::Equal, "Expect '=' after constant name.")?;
+            initializer = Some(self.expression()?);
+        } else if self.find(&[TokenType::Equal]) {
             initializer = Some(self.expression()?);
         }
 
@@ -173,7 +315,38 @@ impl Parser {
             "Expect ';' after variable declaration.",
         )?;
 
-        Ok(Rc::new(Stmt::Var(VarStmt::new(name, initializer))))
+        Ok(Rc::new(Stmt::Var(VarStmt::new(keyword, name, initializer))))
+    }
+
+    fn destructure_declaration(&mut self, keyword: Rc<Token>) -> Result<Rc<Stmt>, ParseError> {
+        let pattern = self.previous();
+        let (close, message) = match pattern.token_type {
+            TokenType::LeftBracket => (TokenType::RightBracket, "Expect ']' after names."),
+            _ => (TokenType::RightBrace, "Expect '}' after names."),
+        };
+
+        let mut names: Vec<Rc<Token>> = Vec::new();
+        loop {
+            names.push(self.consume(&TokenType::Identifier, "Expect variable name.")?);
+            if !self.find(&[TokenType::Comma]) {
+                break;
+            }
+        }
+        self.consume(&close, message)?;
+
+        self.consume(&TokenType::Equal, "Expect '=' after destructuring pattern.")?;
+        let initializer = self.expression()?;
+        self.consume(
+            &TokenType::Semicolon,
+            "Expect ';' after variable declaration.",
+        )?;
+
+        Ok(Rc::new(Stmt::Destructure(DestructureStmt::new(
+            keyword,
+            pattern,
+            names,
+            initializer,
+        ))))
     }
 
     fn statement(&mut self) -> Result<Rc<Stmt>, ParseError> {
@@ -192,6 +365,12 @@ impl Parser {
         if self.find(&[TokenType::Print]) {
             return self.print_statement();
         }
+        if self.find(&[TokenType::Throw]) {
+            return self.throw_statement();
+        }
+        if self.find(&[TokenType::Try]) {
+            return self.try_statement();
+        }
         if self.find(&[TokenType::LeftBrace]) {
             return Ok(Rc::new(Stmt::Block(BlockStmt::new(self.block()?))));
         }
@@ -215,7 +394,7 @@ impl Parser {
         let mut initializer: Option<Rc<Stmt>> = None;
         if self.find(&[TokenType::Semicolon]) {
             // nothing to do...
-        } else if self.find(&[TokenType::Var]) {
+        } else if self.find(&[TokenType::Var, TokenType::Let, TokenType::Const]) {
             initializer = Some(self.var_declaration()?);
         } else {
             initializer = Some(self.expression_statement()?);
@@ -296,10 +475,53 @@ impl Parser {
         ))))
     }
 
+    fn throw_statement(&mut self) -> Result<Rc<Stmt>, ParseError> {
+        let keyword = self.previous();
+        let value = self.expression()?;
+        self.consume(&TokenType::Semicolon, "Expect ';' after thrown value.")?;
+        Ok(Rc::new(Stmt::Throw(ThrowStmt::new(keyword, value))))
+    }
+
+    fn try_statement(&mut self) -> Result<Rc<Stmt>, ParseError> {
+        let keyword = self.previous();
+        self.consume(&TokenType::LeftBrace, "Expect '{' after 'try'.")?;
+        let body = self.block()?;
+
+        let mut handler: Option<CatchClause> = None;
+        if self.find(&[TokenType::Catch]) {
+            self.consume(&TokenType::LeftParen, "Expect '(' after 'catch'.")?;
+            let name = self.consume(&TokenType::Identifier, "Expect exception variable name.")?;
+            self.consume(
+                &TokenType::RightParen,
+                "Expect ')' after exception variable.",
+            )?;
+            self.consume(&TokenType::LeftBrace, "Expect '{' before catch body.")?;
+            handler = Some(CatchClause::new(name, self.block()?));
+        }
+
+        let mut finally: Option<Vec<Rc<Stmt>>> = None;
+        if self.find(&[TokenType::Finally]) {
+            self.consume(&TokenType::LeftBrace, "Expect '{' after 'finally'.")?;
+            finally = Some(self.block()?);
+        }
+
+        if handler.is_none() && finally.is_none() {
+            return Err(CompileError::at(
+                &keyword,
+                "Expect 'catch' or 'finally' after try block.",
+            ));
+        }
+
+        Ok(Rc::new(Stmt::Try(TryStmt::new(
+            keyword, body, handler, finally,
+        ))))
+    }
+
     fn print_statement(&mut self) -> Result<Rc<Stmt>, ParseError> {
+        let keyword = self.previous();
         let value = self.expression()?;
         self.consume(&TokenType::Semicolon, "Expect ';' after value.")?;
-        Ok(Rc::new(Stmt::Print(PrintStmt::new(value))))
+        Ok(Rc::new(Stmt::Print(PrintStmt::new(keyword, value))))
     }
 
     fn expression_statement(&mut self) -> Result<Rc<Stmt>, ParseError> {
@@ -313,29 +535,105 @@ impl Parser {
     }
 
     fn assignment(&mut self) -> Result<Rc<HashExpr>, ParseError> {
-        let expr = self.or()?;
+        let expr = self.conditional()?;
 
         if self.find(&[TokenType::Equal]) {
             let equal = self.previous();
             let value = self.assignment()?;
 
             return match &expr.expr {
-                Expr::Get(expr) => Ok(Rc::new(HashExpr::new(Expr::Set(SetExpr::new(
-                    expr.object.clone(),
+                Expr::Get(expr) if !expr.optional => Ok(Rc::new(HashExpr::new(Expr::Set(
+                    SetExpr::new(expr.object.clone(), expr.name.clone(), value),
+                )))),
+                Expr::Variable(expr) => Ok(Rc::new(HashExpr::new(Expr::Assign(AssignExpr::new(
                     expr.name.clone(),
                     value,
                 ))))),
-                Expr::Variable(expr) => Ok(Rc::new(HashExpr::new(Expr::Assign(AssignExpr::new(
-                    expr.name.clone(),
+                Expr::Index(expr) => Ok(Rc::new(HashExpr::new(Expr::SetIndex(SetIndexExpr::new(
+                    expr.object.clone(),
+                    expr.bracket.clone(),
+                    expr.index.clone(),
                     value,
                 ))))),
-                _ => Err(format!(
-                    "[line {}] <{:?}> : Invalid assignment target.",
-                    equal.line, equal
-                )),
+                Expr::List(expr) if expr.elements.iter().all(|x| Parser::is_target(x)) => {
+                    Ok(Rc::new(HashExpr::new(Expr::AssignList(
+                        AssignListExpr::new(expr.bracket.clone(), expr.elements.clone(), value),
+                    ))))
+                }
+                _ => Err(CompileError::at(&equal, "Invalid assignment target.")),
             };
         }
 
+        if self.find(&[
+            TokenType::PlusEqual,
+            TokenType::MinusEqual,
+            TokenType::StarEqual,
+            TokenType::SlashEqual,
+            TokenType::PercentEqual,
+        ]) {
+            let op = self.previous();
+            let value = self.assignment()?;
+            return Parser::update(expr, op, Some(value), false);
+        }
+
+        Ok(expr)
+    }
+
+    fn update(
+        target: Rc<HashExpr>,
+        op: Rc<Token>,
+        value: Option<Rc<HashExpr>>,
+        prefix: bool,
+    ) -> Result<Rc<HashExpr>, ParseError> {
+        if !Parser::is_target(&target) {
+            return Err(CompileError::at(&op, "Invalid assignment target."));
+        }
+        Ok(Rc::new(HashExpr::new(Expr::Update(UpdateExpr::new(
+            target, op, value, prefix,
+        )))))
+    }
+
+    /// Whether a value can be stored in `target`: a variable, a property or a list element.
+    fn is_target(target: &HashExpr) -> bool {
+        matches!(
+            target.expr,
+            Expr::Get(GetExpr {
+                optional: false,
+                ..
+            }) | Expr::Variable(_)
+                | Expr::Index(_)
+        )
+    }
+
+    fn conditional(&mut self) -> Result<Rc<HashExpr>, ParseError> {
+        let expr = self.coalesce()?;
+
+        if self.find(&[TokenType::Question]) {
+            let then_branch = self.expression()?;
+            self.consume(
+                &TokenType::Colon,
+                "Expect ':' after then branch of conditional expression.",
+            )?;
+            let else_branch = self.conditional()?;
+            return Ok(Rc::new(HashExpr::new(Expr::Conditional(
+                ConditionalExpr::new(expr, then_branch, else_branch),
+            ))));
+        }
+
+        Ok(expr)
+    }
+
+    fn coalesce(&mut self) -> Result<Rc<HashExpr>, ParseError> {
+        let mut expr = self.or()?;
+
+        while self.find(&[TokenType::QuestionQuestion]) {
+            let op = self.previous();
+            let right = self.or()?;
+            expr = Rc::new(HashExpr::new(Expr::Logical(LogicalExpr::new(
+                expr, op, right,
+            ))));
+        }
+
         Ok(expr)
     }
 
@@ -382,7 +680,7 @@ impl Parser {
     }
 
     fn comparison(&mut self) -> Result<Rc<HashExpr>, ParseError> {
-        let mut expr = self.term()?;
+        let mut expr = self.bit_or()?;
 
         while self.find(&[
             TokenType::Greater,
@@ -390,6 +688,62 @@ impl Parser {
             TokenType::Less,
             TokenType::LessEqual,
         ]) {
+            let op = self.previous();
+            let right = self.bit_or()?;
+            expr = Rc::new(HashExpr::new(Expr::Binary(BinaryExpr::new(
+                expr, op, right,
+            ))));
+        }
+
+        Ok(expr)
+    }
+
+    fn bit_or(&mut self) -> Result<Rc<HashExpr>, ParseError> {
+        let mut expr = self.bit_xor()?;
+
+        while self.find(&[TokenType::Pipe]) {
+            let op = self.previous();
+            let right = self.bit_xor()?;
+            expr = Rc::new(HashExpr::new(Expr::Binary(BinaryExpr::new(
+                expr, op, right,
+            ))));
+        }
+
+        Ok(expr)
+    }
+
+    fn bit_xor(&mut self) -> Result<Rc<HashExpr>, ParseError> {
+        let mut expr = self.bit_and()?;
+
+        while self.find(&[TokenType::Caret]) {
+            let op = self.previous();
+            let right = self.bit_and()?;
+            expr = Rc::new(HashExpr::new(Expr::Binary(BinaryExpr::new(
+                expr, op, right,
+            ))));
+        }
+
+        Ok(expr)
+    }
+
+    fn bit_and(&mut self) -> Result<Rc<HashExpr>, ParseError> {
+        let mut expr = self.shift()?;
+
+        while self.find(&[TokenType::Ampersand]) {
+            let op = self.previous();
+            let right = self.shift()?;
+            expr = Rc::new(HashExpr::new(Expr::Binary(BinaryExpr::new(
+                expr, op, right,
+            ))));
+        }
+
+        Ok(expr)
+    }
+
+    fn shift(&mut self) -> Result<Rc<HashExpr>, ParseError> {
+        let mut expr = self.term()?;
+
+        while self.find(&[TokenType::LessLess, TokenType::GreaterGreater]) {
             let op = self.previous();
             let right = self.term()?;
             expr = Rc::new(HashExpr::new(Expr::Binary(BinaryExpr::new(
@@ -417,7 +771,12 @@ impl Parser {
     fn factor(&mut self) -> Result<Rc<HashExpr>, ParseError> {
         let mut expr = self.unary()?;
 
-        while self.find(&[TokenType::Slash, TokenType::Star]) {
+        while self.find(&[
+            TokenType::Slash,
+            TokenType::Star,
+            TokenType::Percent,
+            TokenType::TildeSlash,
+        ]) {
             let op = self.previous();
             let right = self.unary()?;
             expr = Rc::new(HashExpr::new(Expr::Binary(BinaryExpr::new(
@@ -429,7 +788,7 @@ impl Parser {
     }
 
     fn unary(&mut self) -> Result<Rc<HashExpr>, ParseError> {
-        if self.find(&[TokenType::Bang, TokenType::Minus]) {
+        if self.find(&[TokenType::Bang, TokenType::Minus, TokenType::Tilde]) {
             let op = self.previous();
             let right = self.unary()?;
             return Ok(Rc::new(HashExpr::new(Expr::Unary(UnaryExpr::new(
@@ -437,7 +796,38 @@ impl Parser {
             )))));
         }
 
-        self.call()
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Rc<HashExpr>, ParseError> {
+        let expr = self.increment()?;
+
+        // Right-associative, and `-2 ** 2` is `-(2 ** 2)`.
+        if self.find(&[TokenType::StarStar]) {
+            let op = self.previous();
+            let right = self.unary()?;
+            return Ok(Rc::new(HashExpr::new(Expr::Binary(BinaryExpr::new(
+                expr, op, right,
+            )))));
+        }
+
+        Ok(expr)
+    }
+
+    fn increment(&mut self) -> Result<Rc<HashExpr>, ParseError> {
+        if self.find(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let op = self.previous();
+            let target = self.call()?;
+            return Parser::update(target, op, None, true);
+        }
+
+        let expr = self.call()?;
+        if self.find(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let op = self.previous();
+            return Parser::update(expr, op, None, false);
+        }
+
+        Ok(expr)
     }
 
     fn call(&mut self) -> Result<Rc<HashExpr>, ParseError> {
@@ -446,10 +836,18 @@ impl Parser {
         loop {
             if self.find(&[TokenType::LeftParen]) {
                 expr = self.finish_call(expr)?;
-            } else if self.find(&[TokenType::Dot]) {
+            } else if self.find(&[TokenType::Dot, TokenType::QuestionDot]) {
+                let optional = self.previous().token_type == TokenType::QuestionDot;
                 let name =
                     self.consume(&TokenType::Identifier, "Expect property name after '.'.")?;
-                expr = Rc::new(HashExpr::new(Expr::Get(GetExpr::new(expr, name))));
+                expr = Rc::new(HashExpr::new(Expr::Get(GetExpr::new(expr, name, optional))));
+            } else if self.find(&[TokenType::LeftBracket]) {
+                let bracket = self.previous();
+                let index = self.expression()?;
+                self.consume(&TokenType::RightBracket, "Expect ']' after index.")?;
+                expr = Rc::new(HashExpr::new(Expr::Index(IndexExpr::new(
+                    expr, bracket, index,
+                ))));
             } else {
                 break;
             }
@@ -465,9 +863,9 @@ impl Parser {
             loop {
                 if arguments.len() >= Parser::PARAM_MAX_COUNT {
                     let token = self.previous();
-                    return Err(format!(
-                        "[line {}] <{:?}> : Can't have more than 255 arguments.",
-                        token.line, token
+                    return Err(CompileError::at(
+                        &token,
+                        "Can't have more than 255 arguments.",
                     ));
                 }
                 arguments.push(self.expression()?);
@@ -484,6 +882,52 @@ impl Parser {
         )))))
     }
 
+    /// `"a ${b} c"` becomes `"a " + b + " c"`, a string on the left turning `b` into one.
+    fn interpolation(&mut self, start: Rc<Token>) -> Result<Rc<HashExpr>, ParseError> {
+        let literal = |token: &Token| {
+            Rc::new(HashExpr::new(Expr::Literal(LiteralExpr::new(
+                token.literal.clone(),
+            ))))
+        };
+        // Unlike `+`, the parts of an interpolated string can be any value.
+        let plus = |token: &Token| {
+            Rc::new(
+                Token::new(
+                    TokenType::Interpolation,
+                    "+".to_string(),
+                    Object::Nil,
+                    token.line,
+                )
+                .with_column(token.column),
+            )
+        };
+        let concat = |left, op, right| {
+            Rc::new(HashExpr::new(Expr::Binary(BinaryExpr::new(
+                left, op, right,
+            ))))
+        };
+
+        let mut expr = literal(&start);
+        loop {
+            let value = self.expression()?;
+            expr = concat(expr, plus(&start), value);
+
+            let part = match self.find(&[TokenType::Interpolation]) {
+                true => self.previous(),
+                false => self.consume(
+                    &TokenType::String,
+                    "Expect '}' after interpolated expression.",
+                )?,
+            };
+            if part.literal != Object::String(String::new()) {
+                expr = concat(expr, plus(&part), literal(&part));
+            }
+            if part.token_type == TokenType::String {
+                return Ok(expr);
+            }
+        }
+    }
+
     fn primary(&mut self) -> Result<Rc<HashExpr>, ParseError> {
         let token = self.advance();
 
@@ -509,9 +953,14 @@ impl Parser {
             TokenType::Nil => Ok(Rc::new(HashExpr::new(Expr::Literal(LiteralExpr::new(
                 Object::Nil,
             ))))),
+            // The rest of an interpolated string, right after `${` or an operator.
+            TokenType::String | TokenType::Interpolation if token.lexeme.starts_with('}') => {
+                Err(CompileError::at(&token, "Expect expression."))
+            }
             TokenType::Number | TokenType::String => Ok(Rc::new(HashExpr::new(Expr::Literal(
                 LiteralExpr::new(token.literal.clone()),
             )))),
+            TokenType::Interpolation => self.interpolation(token),
             TokenType::LeftParen => {
                 let expr = self.expression()?;
                 self.consume(&TokenType::RightParen, "Expect ')' after expression.")?;
@@ -522,10 +971,22 @@ impl Parser {
             TokenType::Identifier => Ok(Rc::new(HashExpr::new(Expr::Variable(VariableExpr::new(
                 token,
             ))))),
-            _ => Err(format!(
-                "[line {}] <{:?}> : Unexpected token.",
-                token.line, token
-            )),
+            TokenType::LeftBracket => {
+                let mut elements: Vec<Rc<HashExpr>> = Vec::new();
+                if !self.check(&TokenType::RightBracket) {
+                    loop {
+                        elements.push(self.expression()?);
+                        if !self.find(&[TokenType::Comma]) {
+                            break;
+                        }
+                    }
+                }
+                self.consume(&TokenType::RightBracket, "Expect ']' after list elements.")?;
+                Ok(Rc::new(HashExpr::new(Expr::List(ListExpr::new(
+                    token, elements,
+                )))))
+            }
+            _ => Err(CompileError::at(&token, "Unexpected token.")),
         }
     }
 }
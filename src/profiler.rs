@@ -0,0 +1,162 @@
+use crate::{
+    interpreter::{Hook, InterpretError, Interpreter},
+    token::Token,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+const SCRIPT: &str = "<script>";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FunctionStats {
+    pub calls: usize,
+    /// Time spent in the function and everything it called.
+    pub inclusive: Duration,
+    /// Time spent in the function's own statements.
+    pub exclusive: Duration,
+}
+
+struct Call {
+    // `name:line` of the declaration, telling apart methods with the same name.
+    function: String,
+    start: Instant,
+    children: Duration,
+}
+
+/// Counts calls, time per function and statements per line of the script being run.
+pub struct Profiler {
+    pub functions: HashMap<String, FunctionStats>,
+    /// How many statements starting on each line of the script ran.
+    pub lines: BTreeMap<usize, usize>,
+    /// Exclusive time per call stack, outermost function first.
+    pub stacks: HashMap<Vec<String>, Duration>,
+    calls: Vec<Call>,
+}
+
+impl Default for Profiler {
+    fn default() -> Profiler {
+        Profiler::new()
+    }
+}
+
+impl Profiler {
+    /// Starts timing the script right away.
+    pub fn new() -> Profiler {
+        Profiler {
+            functions: HashMap::new(),
+            lines: BTreeMap::new(),
+            stacks: HashMap::new(),
+            calls: vec![Call {
+                function: SCRIPT.to_string(),
+                start: Instant::now(),
+                children: Duration::ZERO,
+            }],
+        }
+    }
+
+    /// Stop timing the script. Calls still running, left by an error, are closed too.
+    pub fn finish(&mut self) {
+        while !self.calls.is_empty() {
+            self.leave();
+        }
+    }
+
+    fn leave(&mut self) {
+        let stack: Vec<String> = self.calls.iter().map(|x| x.function.clone()).collect();
+        let Some(call) = self.calls.pop() else {
+            return;
+        };
+
+        let elapsed = call.start.elapsed();
+        let exclusive = elapsed.saturating_sub(call.children);
+        if let Some(caller) = self.calls.last_mut() {
+            caller.children += elapsed;
+        }
+
+        let stats = self.functions.entry(call.function.clone()).or_default();
+        stats.calls += 1;
+        stats.exclusive += exclusive;
+        // Recursive calls are already inside the time of the outermost one.
+        if !self.calls.iter().any(|x| x.function == call.function) {
+            stats.inclusive += elapsed;
+        }
+        *self.stacks.entry(stack).or_default() += exclusive;
+    }
+
+    /// Functions by exclusive time, then lines by hits, as a table.
+    pub fn report(&self) -> String {
+        let mut functions: Vec<(&String, &FunctionStats)> = self.functions.iter().collect();
+        functions.sort_by(|a, b| b.1.exclusive.cmp(&a.1.exclusive).then(a.0.cmp(b.0)));
+
+        let millis = |x: Duration| x.as_secs_f64() * 1000.0;
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{:<24} {:>8} {:>14} {:>14}",
+            "function", "calls", "inclusive ms", "exclusive ms"
+        );
+        for (name, stats) in functions {
+            let _ = writeln!(
+                out,
+                "{:<24} {:>8} {:>14.3} {:>14.3}",
+                name,
+                stats.calls,
+                millis(stats.inclusive),
+                millis(stats.exclusive)
+            );
+        }
+
+        let mut lines: Vec<(&usize, &usize)> = self.lines.iter().collect();
+        lines.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        let _ = writeln!(out, "\n{:<8} {:>8}", "line", "hits");
+        for (line, hits) in lines {
+            let _ = writeln!(out, "{:<8} {:>8}", line, hits);
+        }
+        out
+    }
+
+    /// One `outer;inner microseconds` line per call stack, the input of flamegraph tools.
+    pub fn collapsed(&self) -> String {
+        let mut stacks: Vec<(String, u128)> = self
+            .stacks
+            .iter()
+            .map(|(stack, time)| (stack.join(";"), time.as_micros()))
+            .collect();
+        stacks.sort();
+
+        let mut out = String::new();
+        for (stack, time) in stacks {
+            let _ = writeln!(out, "{} {}", stack, time);
+        }
+        out
+    }
+}
+
+impl Hook for Profiler {
+    fn statement(&mut self, interpreter: &Interpreter, line: usize) -> Result<(), InterpretError> {
+        // Lines of imported modules would be mixed up with the script's.
+        if interpreter.in_script() {
+            *self.lines.entry(line).or_default() += 1;
+        }
+        Ok(())
+    }
+
+    fn enter(&mut self, _: &Interpreter, name: &Rc<Token>) {
+        self.calls.push(Call {
+            function: format!("{}:{}", name.lexeme, name.line),
+            start: Instant::now(),
+            children: Duration::ZERO,
+        });
+    }
+
+    fn exit(&mut self, _: &Interpreter, _: bool) {
+        // The script's own call is only left by `finish`.
+        if self.calls.len() > 1 {
+            self.leave();
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{
+    class::IsClass,
+    function::Native,
+    interpreter::{InterpretError, Interpreter},
+    object::Object,
+};
+use std::{cell::RefCell, rc::Rc};
+
+pub fn natives() -> Vec<Native> {
+    vec![
+        Native::new("type", 1, type_of),
+        Native::new("numberKind", 1, number_kind),
+        Native::new("instanceOf", 2, instance_of),
+        Native::new("classOf", 1, class_of),
+        Native::new("methods", 1, methods),
+        Native::new("fields", 1, fields),
+        Native::new("hasField", 2, has_field),
+    ]
+}
+
+fn list(mut names: Vec<String>) -> Object {
+    names.sort();
+    names.dedup();
+    Object::List(Rc::new(RefCell::new(
+        names.into_iter().map(Object::String).collect(),
+    )))
+}
+
+fn class_of_object(object: &Object) -> Option<Rc<RefCell<dyn IsClass>>> {
+    match object {
+        Object::Instance(instance) => instance
+            .borrow()
+            .class()
+            .map(|x| x as Rc<RefCell<dyn IsClass>>),
+        _ => None,
+    }
+}
+
+fn type_of(_: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    let name = match arguments[0] {
+        Object::Nil => "nil",
+        Object::Boolean(_) => "boolean",
+        Object::Number(_) | Object::Integer(_) => "number",
+        Object::String(_) => "string",
+        Object::Function(_) => "function",
+        Object::Instance(_) => "instance",
+        Object::Class(_) => "class",
+        Object::Trait(_) => "trait",
+        Object::List(_) => "list",
+        Object::Module(_) => "module",
+    };
+    Ok(Object::String(name.to_string()))
+}
+
+/// `type` calls both numbers "number"; this tells integers from floats.
+fn number_kind(_: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    let kind = match arguments[0] {
+        Object::Integer(_) => "integer",
+        Object::Number(_) => "float",
+        ref value => {
+            return Err(InterpretError::new(format!(
+                "Argument of `numberKind` must be a number, got `{}`.",
+                value
+            )))
+        }
+    };
+    Ok(Object::String(kind.to_string()))
+}
+
+fn instance_of(_: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    let target = match arguments[1] {
+        Object::Class(ref class) => class.clone(),
+        _ => {
+            return Err(InterpretError::new(format!(
+                "Second argument of `instanceOf` must be a class, got `{}`.",
+                arguments[1]
+            )))
+        }
+    };
+
+    let mut current = class_of_object(&arguments[0]);
+    while let Some(class) = current {
+        if Rc::ptr_eq(&class, &target) {
+            return Ok(Object::Boolean(true));
+        }
+        current = class.borrow().superclass();
+    }
+
+    Ok(Object::Boolean(false))
+}
+
+fn class_of(_: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    Ok(class_of_object(&arguments[0]).map_or(Object::Nil, Object::Class))
+}
+
+fn methods(_: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    let mut current = match arguments[0] {
+        Object::Class(ref class) => Some(class.clone()),
+        Object::Instance(_) => class_of_object(&arguments[0]),
+        _ => {
+            return Err(InterpretError::new(format!(
+                "Argument of `methods` must be a class or an instance, got `{}`.",
+                arguments[0]
+            )))
+        }
+    };
+
+    let mut names: Vec<String> = Vec::new();
+    while let Some(class) = current {
+        names.extend(class.borrow().method_names());
+        current = class.borrow().superclass();
+    }
+
+    Ok(list(names))
+}
+
+fn fields(_: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    match arguments[0] {
+        Object::Instance(ref instance) => Ok(list(instance.borrow().field_names())),
+        Object::Module(ref module) => Ok(list(module.borrow().names())),
+        Object::Class(ref class) => Ok(list(class.borrow().field_names())),
+        _ => Err(InterpretError::new(format!(
+            "Argument of `fields` must be a class or an instance, got `{}`.",
+            arguments[0]
+        ))),
+    }
+}
+
+fn has_field(_: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    let name = match arguments[1] {
+        Object::String(ref name) => name,
+        _ => {
+            return Err(InterpretError::new(format!(
+                "Second argument of `hasField` must be a string, got `{}`.",
+                arguments[1]
+            )))
+        }
+    };
+
+    let names = match arguments[0] {
+        Object::Instance(ref instance) => instance.borrow().field_names(),
+        Object::Class(ref class) => class.borrow().field_names(),
+        _ => Vec::new(),
+    };
+
+    Ok(Object::Boolean(names.contains(name)))
+}
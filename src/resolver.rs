@@ -1,12 +1,13 @@
 use crate::{
     expr::{Expr, HashExpr},
     interpreter::Visitor,
+    scanner::CompileError,
     stmt::{FunctionStmt, Stmt},
     token::Token,
 };
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
-type ResolveError = String;
+pub type ResolveError = CompileError;
 
 #[derive(Clone, Copy, PartialEq)]
 enum FunctionType {
@@ -21,13 +22,45 @@ enum ClassType {
     None,
     Class,
     Subclass,
+    Trait,
+}
+
+/// What is known of a name in a scope.
+#[derive(Clone, PartialEq)]
+struct Binding {
+    /// The name in the declaration, `None` for `this` and `super`.
+    token: Option<Rc<Token>>,
+    /// False until the initializer of the variable has been resolved.
+    defined: bool,
+    constant: bool,
+}
+
+impl Binding {
+    const IMPLICIT: Binding = Binding {
+        token: None,
+        defined: true,
+        constant: false,
+    };
+}
+
+/// A name declared in a block, function or other scope below the globals.
+#[derive(Debug, Clone)]
+pub struct LocalDeclaration {
+    pub name: Rc<Token>,
+    /// The declaration of an enclosing scope this one hides, if any.
+    pub shadows: Option<Rc<Token>>,
 }
 
 pub struct Resolver {
-    scopes: Vec<HashMap<String, bool>>,
+    scopes: Vec<HashMap<String, Binding>>,
     locals: Rc<RefCell<HashMap<HashExpr, usize>>>,
+    uses: Vec<(Rc<Token>, Rc<Token>)>,
+    // Uses not declared in any scope yet, which may be of globals declared further down.
+    unresolved: Vec<Rc<Token>>,
+    local_declarations: Vec<LocalDeclaration>,
     current_function: FunctionType,
     current_class: ClassType,
+    strict: bool,
 }
 
 impl Resolver {
@@ -35,33 +68,81 @@ impl Resolver {
         Resolver {
             scopes: Vec::new(),
             locals: Rc::new(RefCell::new(HashMap::new())),
+            uses: Vec::new(),
+            unresolved: Vec::new(),
+            local_declarations: Vec::new(),
             current_function: FunctionType::None,
             current_class: ClassType::None,
+            strict: false,
         }
     }
 
-    pub fn resolve(&mut self, statements: &Vec<Rc<Stmt>>) -> Rc<RefCell<HashMap<HashExpr, usize>>> {
+    /// Follow jlox, where globals can be declared again and read in their own initializer.
+    pub fn strict(mut self) -> Resolver {
+        self.strict = true;
+        self
+    }
+
+    fn at_top_level(&self) -> bool {
+        self.scopes.len() == 1
+    }
+
+    pub fn resolve(
+        &mut self,
+        statements: &[Rc<Stmt>],
+    ) -> Result<Rc<RefCell<HashMap<HashExpr, usize>>>, ResolveError> {
         self.begin_scope();
         for statement in statements {
-            if let Err(err) = self.visit_stmt(statement) {
-                panic!("{}", err);
+            self.visit_stmt(statement)?;
+        }
+
+        // Function bodies can use globals declared after them.
+        for name in std::mem::take(&mut self.unresolved) {
+            let declaration = self.scopes[0]
+                .get(&name.lexeme)
+                .and_then(|x| x.token.clone());
+            if let Some(declaration) = declaration {
+                self.uses.push((name, declaration));
+            }
+        }
+        // Likewise, a local can hide a global declared after it.
+        for local in self.local_declarations.iter_mut() {
+            if local.shadows.is_none() {
+                local.shadows = self.scopes[0]
+                    .get(&local.name.lexeme)
+                    .and_then(|x| x.token.clone());
             }
         }
         self.end_scope();
-        self.locals.clone()
+        Ok(self.locals.clone())
     }
 
-    fn resolve_local(&mut self, hash_expr: &HashExpr, name: &Token) {
+    /// Every use of a declared name, as the token of the use and the token of the declaration.
+    /// Builtins and `this` or `super` have no declaration.
+    pub fn uses(&self) -> &[(Rc<Token>, Rc<Token>)] {
+        &self.uses
+    }
+
+    /// Every name declared below the globals, in the order of the source.
+    pub fn local_declarations(&self) -> &[LocalDeclaration] {
+        &self.local_declarations
+    }
+
+    fn resolve_local(&mut self, hash_expr: &HashExpr, name: &Rc<Token>) {
         let len: usize = self.scopes.len();
         for i in (0..len).rev() {
             let scope = self.scopes.get(i).unwrap();
-            if scope.contains_key(&name.lexeme) {
+            if let Some(binding) = scope.get(&name.lexeme) {
+                if let Some(ref declaration) = binding.token {
+                    self.uses.push((name.clone(), declaration.clone()));
+                }
                 self.locals
                     .borrow_mut()
                     .insert(hash_expr.clone(), len - 1 - i);
                 return;
             }
         }
+        self.unresolved.push(name.clone());
     }
 
     fn resolve_fun(
@@ -94,32 +175,70 @@ impl Resolver {
         self.scopes.pop();
     }
 
-    fn peek(&mut self) -> Option<&mut HashMap<String, bool>> {
+    fn peek(&mut self) -> Option<&mut HashMap<String, Binding>> {
         self.scopes.last_mut()
     }
 
-    fn declare(&mut self, name: &Token) -> Result<(), ResolveError> {
+    fn declare(&mut self, name: &Rc<Token>) -> Result<(), ResolveError> {
+        let redeclarable = self.strict && self.at_top_level();
         if let Some(scope) = self.peek() {
-            if scope.contains_key(&name.lexeme) {
-                return Err(format!(
-                    "[line {}] <{:?}> : Already a variable with this name in this scope.",
-                    name.line, name
+            if scope.contains_key(&name.lexeme) && !redeclarable {
+                return Err(CompileError::at(
+                    name,
+                    "Already a variable with this name in this scope.",
                 ));
             }
 
-            scope.insert(name.lexeme.clone(), false);
+            let binding = Binding {
+                token: Some(name.clone()),
+                defined: false,
+                constant: false,
+            };
+            scope.insert(name.lexeme.clone(), binding);
+        }
+
+        if !self.at_top_level() {
+            let enclosing = &self.scopes[..self.scopes.len() - 1];
+            let shadows = enclosing
+                .iter()
+                .rev()
+                .find_map(|x| x.get(&name.lexeme))
+                .and_then(|x| x.token.clone());
+            self.local_declarations.push(LocalDeclaration {
+                name: name.clone(),
+                shadows,
+            });
         }
 
         Ok(())
     }
 
     fn define(&mut self, name: &Token) -> Result<(), ResolveError> {
-        if let Some(scope) = self.peek() {
-            scope.insert(name.lexeme.clone(), true);
+        if let Some(binding) = self.peek().and_then(|x| x.get_mut(&name.lexeme)) {
+            binding.defined = true;
         }
 
         Ok(())
     }
+
+    fn define_constant(&mut self, name: &Token) -> Result<(), ResolveError> {
+        self.define(name)?;
+        if let Some(binding) = self.peek().and_then(|x| x.get_mut(&name.lexeme)) {
+            binding.constant = true;
+        }
+
+        Ok(())
+    }
+
+    fn assignable(&self, name: &Token) -> Result<(), ResolveError> {
+        match self.scopes.iter().rev().find_map(|x| x.get(&name.lexeme)) {
+            Some(binding) if binding.constant => Err(CompileError::at(
+                name,
+                format!("Can't assign to constant `{}`.", name.lexeme),
+            )),
+            _ => Ok(()),
+        }
+    }
 }
 
 impl Visitor<(), ResolveError> for Resolver {
@@ -150,14 +269,32 @@ impl Visitor<(), ResolveError> for Resolver {
             }
             Expr::Assign(expr) => {
                 self.visit_expr(&expr.value)?;
+                self.assignable(&expr.name)?;
                 self.resolve_local(hash_expr, &expr.name);
                 Ok(())
             }
+            Expr::AssignList(expr) => {
+                self.visit_expr(&expr.value)?;
+                for target in &expr.targets {
+                    match &target.expr {
+                        Expr::Variable(variable) => {
+                            self.assignable(&variable.name)?;
+                            self.resolve_local(target, &variable.name);
+                        }
+                        _ => self.visit_expr(target)?,
+                    }
+                }
+                Ok(())
+            }
             Expr::Variable(expr) => {
-                if let Some(false) = self.peek().and_then(|x| x.get(&expr.name.lexeme)) {
-                    return Err(format!(
-                        "[line {}] <{:?}> : Can't read local variable in its own initializer.",
-                        expr.name.line, expr.name
+                let declaring = self
+                    .peek()
+                    .and_then(|x| x.get(&expr.name.lexeme))
+                    .is_some_and(|x| !x.defined);
+                if declaring && !(self.strict && self.at_top_level()) {
+                    return Err(CompileError::at(
+                        &expr.name,
+                        "Can't read local variable in its own initializer.",
                     ));
                 }
                 self.resolve_local(hash_expr, &expr.name);
@@ -167,16 +304,49 @@ impl Visitor<(), ResolveError> for Resolver {
                 self.visit_expr(&expr.object)?;
                 Ok(())
             }
+            Expr::List(expr) => {
+                for element in &expr.elements {
+                    self.visit_expr(element)?
+                }
+                Ok(())
+            }
+            Expr::Index(expr) => {
+                self.visit_expr(&expr.object)?;
+                self.visit_expr(&expr.index)?;
+                Ok(())
+            }
+            Expr::SetIndex(expr) => {
+                self.visit_expr(&expr.value)?;
+                self.visit_expr(&expr.object)?;
+                self.visit_expr(&expr.index)?;
+                Ok(())
+            }
             Expr::Set(expr) => {
                 self.visit_expr(&expr.value)?;
                 self.visit_expr(&expr.object)?;
                 Ok(())
             }
+            Expr::Conditional(expr) => {
+                self.visit_expr(&expr.condition)?;
+                self.visit_expr(&expr.then_branch)?;
+                self.visit_expr(&expr.else_branch)?;
+                Ok(())
+            }
+            Expr::Update(expr) => {
+                if let Some(ref value) = expr.value {
+                    self.visit_expr(value)?;
+                }
+                if let Expr::Variable(ref target) = expr.target.expr {
+                    self.assignable(&target.name)?;
+                }
+                self.visit_expr(&expr.target)?;
+                Ok(())
+            }
             Expr::This(expr) => {
                 if self.current_class == ClassType::None {
-                    return Err(format!(
-                        "[line {}] <{:?}> : Can't use 'this' outside of a class.",
-                        expr.keyword.line, expr.keyword
+                    return Err(CompileError::at(
+                        &expr.keyword,
+                        "Can't use 'this' outside of a class.",
                     ));
                 }
 
@@ -185,14 +355,14 @@ impl Visitor<(), ResolveError> for Resolver {
             }
             Expr::Super(expr) => {
                 if self.current_class == ClassType::None {
-                    return Err(format!(
-                        "[line {}] <{:?}> : Can't use 'super' outside of a class.",
-                        expr.keyword.line, expr.keyword
+                    return Err(CompileError::at(
+                        &expr.keyword,
+                        "Can't use 'super' outside of a class.",
                     ));
-                } else if self.current_class != ClassType::Subclass {
-                    return Err(format!(
-                        "[line {}] <{:?}> : Can't use 'super' in a class with no superclass.",
-                        expr.keyword.line, expr.keyword
+                } else if self.current_class == ClassType::Class {
+                    return Err(CompileError::at(
+                        &expr.keyword,
+                        "Can't use 'super' in a class with no superclass.",
                     ));
                 }
 
@@ -211,9 +381,9 @@ impl Visitor<(), ResolveError> for Resolver {
             }
             Stmt::Return(stmt) => {
                 if self.current_function == FunctionType::None {
-                    return Err(format!(
-                        "[line {}] <{:?}> : Can't return from top-level code.",
-                        stmt.keyword.line, stmt.keyword
+                    return Err(CompileError::at(
+                        &stmt.keyword,
+                        "Can't return from top-level code.",
                     ));
                 }
 
@@ -222,9 +392,9 @@ impl Visitor<(), ResolveError> for Resolver {
                         Expr::This(_) => (), // Skip
                         _ => {
                             if self.current_function == FunctionType::Initializer {
-                                return Err(format!(
-                                    "[line {}] <{:?}> : Can't return a value from an initializer.",
-                                    stmt.keyword.line, stmt.keyword
+                                return Err(CompileError::at(
+                                    &stmt.keyword,
+                                    "Can't return a value from an initializer.",
                                 ));
                             }
                         }
@@ -255,7 +425,23 @@ impl Visitor<(), ResolveError> for Resolver {
                 if let Some(ref initializer) = stmt.initializer {
                     self.visit_expr(initializer)?
                 }
-                self.define(&stmt.name)?;
+                match stmt.is_constant() {
+                    true => self.define_constant(&stmt.name)?,
+                    false => self.define(&stmt.name)?,
+                }
+                Ok(())
+            }
+            Stmt::Destructure(stmt) => {
+                for name in &stmt.names {
+                    self.declare(name)?;
+                }
+                self.visit_expr(&stmt.initializer)?;
+                for name in &stmt.names {
+                    match stmt.is_constant() {
+                        true => self.define_constant(name)?,
+                        false => self.define(name)?,
+                    }
+                }
                 Ok(())
             }
             Stmt::Block(stmt) => {
@@ -275,25 +461,31 @@ impl Visitor<(), ResolveError> for Resolver {
                 if let Some(ref hash_expr) = stmt.superclass {
                     if let Expr::Variable(ref expr) = hash_expr.expr {
                         if expr.name.lexeme == stmt.name.lexeme {
-                            return Err(format!(
-                                "[line {}] <{:?}> : A class can't inherit from itself.",
-                                stmt.name.line, stmt.name
+                            return Err(CompileError::at(
+                                &stmt.name,
+                                "A class can't inherit from itself.",
                             ));
                         }
                     }
 
                     self.current_class = ClassType::Subclass;
                     self.visit_expr(hash_expr)?;
+                }
+
+                for hash_expr in &stmt.traits {
+                    self.visit_expr(hash_expr)?;
+                }
 
+                if stmt.superclass.is_some() {
                     self.begin_scope();
                     if let Some(scope) = self.peek() {
-                        scope.insert("super".to_string(), true);
+                        scope.insert("super".to_string(), Binding::IMPLICIT);
                     }
                 }
 
                 self.begin_scope();
                 if let Some(scope) = self.peek() {
-                    scope.insert("this".to_string(), true);
+                    scope.insert("this".to_string(), Binding::IMPLICIT);
                 }
 
                 for method in &stmt.methods {
@@ -314,6 +506,97 @@ impl Visitor<(), ResolveError> for Resolver {
 
                 self.define(&stmt.name)?;
 
+                self.current_class = enclosing_class;
+                Ok(())
+            }
+            Stmt::Throw(stmt) => self.visit_expr(&stmt.value),
+            Stmt::Import(stmt) => {
+                if self.scopes.len() > 1 || self.current_function != FunctionType::None {
+                    return Err(CompileError::at(
+                        &stmt.keyword,
+                        "Can only import at top level.",
+                    ));
+                }
+
+                for name in stmt.name.iter().chain(&stmt.names) {
+                    self.declare(name)?;
+                    self.define(name)?;
+                }
+                Ok(())
+            }
+            Stmt::Test(stmt) => {
+                if self.scopes.len() > 1 || self.current_function != FunctionType::None {
+                    return Err(CompileError::at(
+                        &stmt.keyword,
+                        "Can only declare tests at top level.",
+                    ));
+                }
+
+                self.begin_scope();
+                for statement in &stmt.body {
+                    self.visit_stmt(statement)?
+                }
+                self.end_scope();
+                Ok(())
+            }
+            Stmt::Try(stmt) => {
+                self.begin_scope();
+                for statement in &stmt.body {
+                    self.visit_stmt(statement)?
+                }
+                self.end_scope();
+
+                if let Some(ref handler) = stmt.handler {
+                    self.begin_scope();
+                    self.declare(&handler.name)?;
+                    self.define(&handler.name)?;
+                    for statement in &handler.body {
+                        self.visit_stmt(statement)?
+                    }
+                    self.end_scope();
+                }
+
+                if let Some(ref finally) = stmt.finally {
+                    self.begin_scope();
+                    for statement in finally {
+                        self.visit_stmt(statement)?
+                    }
+                    self.end_scope();
+                }
+
+                Ok(())
+            }
+            Stmt::Trait(stmt) => {
+                let enclosing_class = self.current_class;
+                self.current_class = ClassType::Trait;
+
+                self.declare(&stmt.name)?;
+                self.define(&stmt.name)?;
+
+                // Trait methods see the superclass of whichever class mixes them in.
+                self.begin_scope();
+                if let Some(scope) = self.peek() {
+                    scope.insert("super".to_string(), Binding::IMPLICIT);
+                }
+
+                self.begin_scope();
+                if let Some(scope) = self.peek() {
+                    scope.insert("this".to_string(), Binding::IMPLICIT);
+                }
+
+                for method in &stmt.methods {
+                    let mut declaration = FunctionType::Method;
+
+                    if method.name.lexeme == "init" {
+                        declaration = FunctionType::Initializer;
+                    }
+
+                    self.resolve_fun(method, declaration)?
+                }
+
+                self.end_scope();
+                self.end_scope();
+
                 self.current_class = enclosing_class;
                 Ok(())
             }
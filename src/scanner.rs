@@ -2,12 +2,37 @@ use crate::{
     object::Object,
     token::{Token, TokenType},
 };
-use std::{collections::HashMap, f64, rc::Rc, sync::LazyLock};
+use std::{collections::HashMap, f64, fmt, rc::Rc, sync::LazyLock};
 
-pub fn scan_tokens(code: &str) -> Vec<Rc<Token>> {
+pub fn scan_tokens(code: &str) -> Result<Vec<Rc<Token>>, ScanError> {
+    scan(code, false, false)
+}
+
+/// Like `scan_tokens`, but `//` comments are kept as `Comment` tokens.
+pub fn scan_tokens_with_comments(code: &str) -> Result<Vec<Rc<Token>>, ScanError> {
+    scan(code, true, false)
+}
+
+/// Scan like jlox: the words only rlox reserves are identifiers, and `123.` or `123abc` are
+/// a number followed by other tokens.
+pub fn scan_tokens_strict(code: &str) -> Result<Vec<Rc<Token>>, ScanError> {
+    scan(code, false, true)
+}
+
+/// The reserved words, in alphabetical order.
+pub fn keywords() -> Vec<&'static str> {
+    let table = Scanner::KEYWORDS;
+    let mut keywords: Vec<&'static str> = table.keys().cloned().collect();
+    keywords.sort();
+    keywords
+}
+
+fn scan(code: &str, keep_comments: bool, strict: bool) -> Result<Vec<Rc<Token>>, ScanError> {
     let chars: Vec<char> = code.chars().collect(); // utf-8
 
     let mut scanner = Scanner::new(chars);
+    scanner.keep_comments = keep_comments;
+    scanner.strict = strict;
     let mut tokens: Vec<Rc<Token>> = Vec::new();
 
     while !scanner.is_at_end() {
@@ -17,22 +42,73 @@ pub fn scan_tokens(code: &str) -> Vec<Rc<Token>> {
                     tokens.push(Rc::new(token))
                 }
             }
-            Err(err) => panic!("{}", err),
+            Err(err) => return Err(err),
         }
     }
 
+    if !scanner.interpolations.is_empty() {
+        return Err(CompileError::new(scanner.line, "Unterminated string."));
+    }
     tokens.push(Rc::new(scanner.eof()));
 
-    tokens
+    Ok(tokens)
 }
 
-type ScanError = String;
+/// An error of the scanner, parser or resolver, which stops the code from running at all.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompileError {
+    pub line: usize,
+    /// Where the error is, unless the scanner found it before it had a token.
+    pub token: Option<Token>,
+    pub message: String,
+}
+
+impl CompileError {
+    pub fn new(line: usize, message: impl Into<String>) -> CompileError {
+        CompileError {
+            line,
+            token: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn at(token: &Token, message: impl Into<String>) -> CompileError {
+        CompileError {
+            line: token.line,
+            token: Some(token.clone()),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.token {
+            Some(ref token) => write!(fmt, "[line {}] <{:?}> : {}", self.line, token, self.message),
+            None => write!(fmt, "[line {}] : {}", self.line, self.message),
+        }
+    }
+}
+
+impl From<CompileError> for String {
+    fn from(error: CompileError) -> String {
+        error.to_string()
+    }
+}
+
+pub type ScanError = CompileError;
 
 struct Scanner {
     chars: Vec<char>,
     start: usize,
     current: usize,
     line: usize,
+    line_start: usize,
+    column: usize,
+    keep_comments: bool,
+    strict: bool,
+    // Braces opened inside each `${` of the strings being scanned, innermost last.
+    interpolations: Vec<usize>,
 }
 
 impl Scanner {
@@ -40,25 +116,47 @@ impl Scanner {
         let mut m = HashMap::new();
 
         m.insert("and", TokenType::And);
+        m.insert("catch", TokenType::Catch);
         m.insert("class", TokenType::Class);
+        m.insert("const", TokenType::Const);
         m.insert("else", TokenType::Else);
         m.insert("false", TokenType::False);
+        m.insert("finally", TokenType::Finally);
         m.insert("for", TokenType::For);
         m.insert("fun", TokenType::Fun);
         m.insert("if", TokenType::If);
+        m.insert("import", TokenType::Import);
+        m.insert("let", TokenType::Let);
         m.insert("nil", TokenType::Nil);
         m.insert("or", TokenType::Or);
         m.insert("print", TokenType::Print);
         m.insert("return", TokenType::Return);
         m.insert("super", TokenType::Super);
         m.insert("this", TokenType::This);
+        m.insert("throw", TokenType::Throw);
+        m.insert("trait", TokenType::Trait);
         m.insert("true", TokenType::True);
+        m.insert("try", TokenType::Try);
         m.insert("var", TokenType::Var);
         m.insert("while", TokenType::While);
+        m.insert("with", TokenType::With);
 
         m
     });
 
+    /// Keywords jlox doesn't have.
+    const EXTENSIONS: [TokenType; 9] = [
+        TokenType::Catch,
+        TokenType::Const,
+        TokenType::Finally,
+        TokenType::Import,
+        TokenType::Let,
+        TokenType::Throw,
+        TokenType::Trait,
+        TokenType::Try,
+        TokenType::With,
+    ];
+
     fn is_digit(char: char) -> bool {
         char >= '0' && char <= '9'
     }
@@ -77,11 +175,17 @@ impl Scanner {
             start: 0,
             current: 0,
             line: 1,
+            line_start: 0,
+            column: 1,
+            keep_comments: false,
+            strict: false,
+            interpolations: Vec::new(),
         }
     }
 
     fn eof(&self) -> Token {
         Token::new(TokenType::Eof, "".to_string(), Object::Nil, self.line)
+            .with_column(self.current - self.line_start + 1)
     }
 
     fn is_at_end(&self) -> bool {
@@ -120,38 +224,203 @@ impl Scanner {
         let slice = &self.chars[self.start..self.current];
         let lexeme = String::from_iter(slice);
 
-        let literal = match token_type {
-            TokenType::Number => Object::Number(lexeme.parse::<f64>().unwrap()),
-            TokenType::String => Object::String(String::from_iter(
-                &self.chars[self.start + 1..self.current - 1],
-            )),
-            _ => Object::Nil,
-        };
+        let token = Token::new(token_type, lexeme, Object::Nil, self.line).with_column(self.column);
+        Ok(Some(token))
+    }
 
-        Ok(Some(Token::new(token_type, lexeme, literal, self.line)))
+    fn literal_token(
+        &mut self,
+        token_type: TokenType,
+        literal: Object,
+    ) -> Result<Option<Token>, ScanError> {
+        let lexeme = String::from_iter(&self.chars[self.start..self.current]);
+        let token = Token::new(token_type, lexeme, literal, self.line).with_column(self.column);
+        Ok(Some(token))
     }
 
+    /// The rest of a string, after its opening `"` or the `}` closing an interpolation.
     fn string(&mut self) -> Result<Option<Token>, ScanError> {
+        let mut value = String::new();
         while let Some(char) = self.peek() {
+            self.advance();
             match char {
-                '"' => {
-                    self.advance();
-                    return self.token(TokenType::String);
-                }
+                '"' => return self.literal_token(TokenType::String, Object::String(value)),
                 '\n' => {
-                    self.line += 1;
-                    self.advance();
+                    self.new_line();
+                    value.push(char);
                 }
-                _ => {
+                // jlox has neither escapes nor interpolation.
+                '\\' if !self.strict => value.push(self.escape()?),
+                '$' if !self.strict && self.find('{') => {
+                    self.interpolations.push(0);
+                    return self.literal_token(TokenType::Interpolation, Object::String(value));
+                }
+                _ => value.push(char),
+            }
+        }
+
+        Err(CompileError::new(self.line, "Unterminated string."))
+    }
+
+    fn escape(&mut self) -> Result<char, ScanError> {
+        if self.is_at_end() {
+            return Err(CompileError::new(self.line, "Unterminated string."));
+        }
+
+        let char = self.advance();
+        match char {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            '\\' | '"' | '$' => Ok(char),
+            'u' if self.find('{') => {
+                let start = self.current;
+                while self.peek().is_some_and(|x| x.is_ascii_hexdigit()) {
                     self.advance();
                 }
+                let digits = String::from_iter(&self.chars[start..self.current]);
+                let code = match digits.len() {
+                    1..=6 => u32::from_str_radix(&digits, 16).ok(),
+                    _ => None,
+                };
+                let closed = self.find('}');
+                match code.and_then(char::from_u32) {
+                    Some(char) if closed => Ok(char),
+                    _ => Err(CompileError::new(
+                        self.line,
+                        format!(
+                            "Invalid unicode escape `\\u{{{}{}`.",
+                            digits,
+                            if closed { "}" } else { "" }
+                        ),
+                    )),
+                }
             }
+            char => Err(CompileError::new(
+                self.line,
+                format!("Invalid escape sequence `\\{}`.", char),
+            )),
         }
+    }
 
-        Err(format!("[line {}] : Unterminated string.", self.line))
+    /// `"""..."""`, kept as written: no escapes, no interpolation, newlines included.
+    fn raw_string(&mut self) -> Result<Option<Token>, ScanError> {
+        self.advance();
+        self.advance();
+
+        let start = self.current;
+        while self.current < self.chars.len() {
+            if self.chars[self.current..].starts_with(&['"', '"', '"']) {
+                let value = String::from_iter(&self.chars[start..self.current]);
+                self.current += 3;
+                return self.literal_token(TokenType::String, Object::String(value));
+            }
+            if self.advance() == '\n' {
+                self.new_line();
+            }
+        }
+
+        Err(CompileError::new(self.line, "Unterminated string."))
     }
 
     fn number(&mut self) -> Result<Option<Token>, ScanError> {
+        if self.strict {
+            return self.strict_number();
+        }
+
+        let invalid = |line| CompileError::new(line, "Invalid number.");
+        let radix = match (self.previous(), self.peek()) {
+            ('0', Some('x' | 'X')) => 16,
+            ('0', Some('b' | 'B')) => 2,
+            ('0', Some('o' | 'O')) => 8,
+            _ => 10,
+        };
+
+        let mut float = false;
+        let digits = if radix != 10 {
+            self.advance();
+            let digits = self.digits(radix).ok_or_else(|| invalid(self.line))?;
+            if digits.is_empty() {
+                return Err(invalid(self.line));
+            }
+            digits
+        } else {
+            self.current = self.start;
+            let mut digits = self.digits(10).ok_or_else(|| invalid(self.line))?;
+            if self.peek() == Some('.')
+                && self
+                    .peek_at(self.current + 1)
+                    .is_some_and(Scanner::is_digit)
+            {
+                self.advance();
+                let fraction = self.digits(10).ok_or_else(|| invalid(self.line))?;
+                digits = format!("{}.{}", digits, fraction);
+                float = true;
+            }
+            if let Some('e' | 'E') = self.peek() {
+                self.advance();
+                let sign = match self.peek() {
+                    Some(sign @ ('+' | '-')) => {
+                        self.advance();
+                        sign.to_string()
+                    }
+                    _ => String::new(),
+                };
+                let exponent = self.digits(10).ok_or_else(|| invalid(self.line))?;
+                if exponent.is_empty() {
+                    return Err(invalid(self.line));
+                }
+                digits = format!("{}e{}{}", digits, sign, exponent);
+                float = true;
+            }
+            digits
+        };
+
+        if let Some(char) = self.peek() {
+            // `123abc`, `123.` or `0xfg`
+            if Scanner::is_alpha_numeric(char) || char == '.' {
+                return Err(invalid(self.line));
+            }
+        }
+
+        let literal = match float {
+            true => Object::Number(digits.parse::<f64>().unwrap()),
+            false => match i64::from_str_radix(&digits, radix) {
+                Ok(n) => Object::Integer(n),
+                Err(_) => {
+                    return Err(CompileError::new(
+                        self.line,
+                        "Integer literal is too large.",
+                    ))
+                }
+            },
+        };
+        self.literal_token(TokenType::Number, literal)
+    }
+
+    /// Digits of `radix`, which `_` can separate, without the separators. `None` when a `_` is
+    /// not between two digits.
+    fn digits(&mut self, radix: u32) -> Option<String> {
+        let mut digits = String::new();
+        while let Some(char) = self.peek() {
+            if char.is_digit(radix) {
+                digits.push(char);
+            } else if char == '_' {
+                let next = self.peek_at(self.current + 1);
+                if digits.is_empty() || !next.is_some_and(|x| x.is_digit(radix)) {
+                    return None;
+                }
+            } else {
+                break;
+            }
+            self.advance();
+        }
+        Some(digits)
+    }
+
+    /// Only decimal numbers, which are all floats.
+    fn strict_number(&mut self) -> Result<Option<Token>, ScanError> {
         while self.peek().map_or(false, Scanner::is_digit) {
             self.advance();
         }
@@ -167,14 +436,8 @@ impl Scanner {
             }
         }
 
-        if let Some(char) = self.peek() {
-            // `123abc` or `123.`
-            if Scanner::is_alpha(char) || char == '.' {
-                return Err(format!("[line {}] : Invalid number.", self.line));
-            }
-        }
-
-        self.token(TokenType::Number)
+        let lexeme = String::from_iter(&self.chars[self.start..self.current]);
+        self.literal_token(TokenType::Number, Object::Number(lexeme.parse().unwrap()))
     }
 
     fn identifier(&mut self) -> Result<Option<Token>, ScanError> {
@@ -188,32 +451,123 @@ impl Scanner {
         let token_type = Scanner::KEYWORDS
             .get(lexeme.as_str())
             .cloned()
+            .filter(|x| !self.strict || !Scanner::EXTENSIONS.contains(x))
             .unwrap_or(TokenType::Identifier);
 
         self.token(token_type)
     }
 
+    fn new_line(&mut self) {
+        self.line += 1;
+        self.line_start = self.current;
+    }
+
     fn scan_token(&mut self) -> Result<Option<Token>, ScanError> {
         self.start = self.current;
+        self.column = self.start - self.line_start + 1;
 
         let char = self.advance();
 
         match char {
             '(' => self.token(TokenType::LeftParen),
             ')' => self.token(TokenType::RightParen),
-            '{' => self.token(TokenType::LeftBrace),
-            '}' => self.token(TokenType::RightBrace),
-
+            '{' => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.token(TokenType::LeftBrace)
+            }
+            '}' => match self.interpolations.last_mut() {
+                // The end of an interpolated expression, the string goes on.
+                Some(0) => {
+                    self.interpolations.pop();
+                    self.string()
+                }
+                Some(depth) => {
+                    *depth -= 1;
+                    self.token(TokenType::RightBrace)
+                }
+                None => self.token(TokenType::RightBrace),
+            },
+            '[' => self.token(TokenType::LeftBracket),
+            ']' => self.token(TokenType::RightBracket),
+
+            '+' if !self.strict => {
+                if self.find('+') {
+                    self.token(TokenType::PlusPlus)
+                } else if self.find('=') {
+                    self.token(TokenType::PlusEqual)
+                } else {
+                    self.token(TokenType::Plus)
+                }
+            }
+            '-' if !self.strict => {
+                if self.find('-') {
+                    self.token(TokenType::MinusMinus)
+                } else if self.find('=') {
+                    self.token(TokenType::MinusEqual)
+                } else {
+                    self.token(TokenType::Minus)
+                }
+            }
+            '*' if !self.strict => {
+                if self.find('*') {
+                    self.token(TokenType::StarStar)
+                } else if self.find('=') {
+                    self.token(TokenType::StarEqual)
+                } else {
+                    self.token(TokenType::Star)
+                }
+            }
+            '%' if !self.strict => {
+                if self.find('=') {
+                    self.token(TokenType::PercentEqual)
+                } else {
+                    self.token(TokenType::Percent)
+                }
+            }
             '+' => self.token(TokenType::Plus),
             '-' => self.token(TokenType::Minus),
             '*' => self.token(TokenType::Star),
+            '&' if !self.strict => self.token(TokenType::Ampersand),
+            '|' if !self.strict => self.token(TokenType::Pipe),
+            '^' if !self.strict => self.token(TokenType::Caret),
+            ':' if !self.strict => self.token(TokenType::Colon),
+            '?' if !self.strict => {
+                if self.find('?') {
+                    self.token(TokenType::QuestionQuestion)
+                // `x?.5:1` is a conditional with a number, not `?.`.
+                } else if self.peek() == Some('.')
+                    && !self
+                        .peek_at(self.current + 1)
+                        .is_some_and(Scanner::is_digit)
+                {
+                    self.advance();
+                    self.token(TokenType::QuestionDot)
+                } else {
+                    self.token(TokenType::Question)
+                }
+            }
+            '~' if !self.strict => {
+                if self.find('/') {
+                    self.token(TokenType::TildeSlash)
+                } else {
+                    self.token(TokenType::Tilde)
+                }
+            }
 
             '/' => {
                 if self.find('/') {
                     while self.peek().map_or(false, |x| x != '\n') {
                         self.advance();
                     }
-                    Ok(None)
+                    if self.keep_comments {
+                        self.token(TokenType::Comment)
+                    } else {
+                        Ok(None)
+                    }
+                } else if !self.strict && self.find('=') {
+                    self.token(TokenType::SlashEqual)
                 } else {
                     self.token(TokenType::Slash)
                 }
@@ -236,6 +590,8 @@ impl Scanner {
             '>' => {
                 if self.find('=') {
                     self.token(TokenType::GreaterEqual)
+                } else if !self.strict && self.find('>') {
+                    self.token(TokenType::GreaterGreater)
                 } else {
                     self.token(TokenType::Greater)
                 }
@@ -243,21 +599,31 @@ impl Scanner {
             '<' => {
                 if self.find('=') {
                     self.token(TokenType::LessEqual)
+                } else if !self.strict && self.find('<') {
+                    self.token(TokenType::LessLess)
                 } else {
                     self.token(TokenType::Less)
                 }
             }
 
+            // `.5`
+            '.' if !self.strict && self.peek().is_some_and(Scanner::is_digit) => self.number(),
             '.' => self.token(TokenType::Dot),
             ',' => self.token(TokenType::Comma),
             ';' => self.token(TokenType::Semicolon),
 
             ' ' | '\r' | '\t' => Ok(None),
             '\n' => {
-                self.line += 1;
+                self.new_line();
                 Ok(None)
             }
 
+            '"' if !self.strict
+                && self.peek() == Some('"')
+                && self.peek_at(self.current + 1) == Some('"') =>
+            {
+                self.raw_string()
+            }
             '"' => self.string(),
 
             char => {
@@ -266,7 +632,7 @@ impl Scanner {
                 } else if Scanner::is_alpha(char) {
                     self.identifier()
                 } else {
-                    Err(format!("[line {}] : Unknown character.", self.line))
+                    Err(CompileError::new(self.line, "Unknown character."))
                 }
             }
         }
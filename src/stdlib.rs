@@ -0,0 +1,246 @@
+use crate::{
+    function::Native,
+    interpreter::{InterpretError, Interpreter},
+    object::Object,
+};
+use std::{
+    cell::RefCell,
+    fs,
+    io::{self, Write},
+    rc::Rc,
+};
+
+/// Source of a module bundled with the binary, looked up before the filesystem.
+pub fn source(name: &str) -> Option<&'static str> {
+    match name {
+        "math" => Some(include_str!("stdlib/math.lox")),
+        "string" => Some(include_str!("stdlib/string.lox")),
+        "list" => Some(include_str!("stdlib/list.lox")),
+        "io" => Some(include_str!("stdlib/io.lox")),
+        _ => None,
+    }
+}
+
+/// Primitives the bundled modules are written on top of. They are only visible to stdlib
+/// modules, which wrap them in plain Lox functions.
+pub fn natives() -> Vec<Native> {
+    vec![
+        Native::new("__floor", 1, |_, args| math(&args, "floor", f64::floor)),
+        Native::new("__ceil", 1, |_, args| math(&args, "ceil", f64::ceil)),
+        Native::new("__round", 1, |_, args| math(&args, "round", f64::round)),
+        Native::new("__sqrt", 1, |_, args| math(&args, "sqrt", f64::sqrt)),
+        Native::new("__sin", 1, |_, args| math(&args, "sin", f64::sin)),
+        Native::new("__cos", 1, |_, args| math(&args, "cos", f64::cos)),
+        Native::new("__tan", 1, |_, args| math(&args, "tan", f64::tan)),
+        Native::new("__log", 1, |_, args| math(&args, "log", f64::ln)),
+        Native::new("__exp", 1, |_, args| math(&args, "exp", f64::exp)),
+        Native::new("__pow", 2, pow),
+        Native::new("__len", 1, len),
+        Native::new("__substring", 3, substring),
+        Native::new("__index_of", 2, index_of),
+        Native::new("__upper", 1, |_, args| {
+            Ok(Object::String(string(&args[0], "upper")?.to_uppercase()))
+        }),
+        Native::new("__lower", 1, |_, args| {
+            Ok(Object::String(string(&args[0], "lower")?.to_lowercase()))
+        }),
+        Native::new("__trim", 1, |_, args| {
+            Ok(Object::String(string(&args[0], "trim")?.trim().to_string()))
+        }),
+        Native::new("__split", 2, split),
+        Native::new("__parse_number", 1, parse_number),
+        Native::new("__list_push", 2, list_push),
+        Native::new("__list_pop", 1, list_pop),
+        Native::new("__list_insert", 3, list_insert),
+        Native::new("__list_remove", 2, list_remove),
+        Native::new("__list_slice", 3, list_slice),
+        Native::new("__write", 1, write),
+        Native::new("__read_line", 0, read_line),
+        Native::new("__read_file", 1, read_file),
+        Native::new("__write_file", 2, write_file),
+    ]
+}
+
+fn number(value: &Object, name: &str) -> Result<f64, InterpretError> {
+    value.as_float().ok_or_else(|| {
+        InterpretError::new(format!(
+            "Argument of `{}` must be a number, got `{}`.",
+            name, value
+        ))
+    })
+}
+
+fn string<'a>(value: &'a Object, name: &str) -> Result<&'a String, InterpretError> {
+    match value {
+        Object::String(s) => Ok(s),
+        _ => Err(InterpretError::new(format!(
+            "Argument of `{}` must be a string, got `{}`.",
+            name, value
+        ))),
+    }
+}
+
+fn list(value: &Object, name: &str) -> Result<Rc<RefCell<Vec<Object>>>, InterpretError> {
+    match value {
+        Object::List(l) => Ok(l.clone()),
+        _ => Err(InterpretError::new(format!(
+            "Argument of `{}` must be a list, got `{}`.",
+            name, value
+        ))),
+    }
+}
+
+/// Clamp a Lox number into `0..=len`, counting negative numbers from the end.
+fn position(value: &Object, len: usize, name: &str) -> Result<usize, InterpretError> {
+    let n = number(value, name)?;
+    if n.fract() != 0.0 {
+        return Err(InterpretError::new("Index must be an integer."));
+    }
+    let n = if n < 0.0 { len as f64 + n } else { n };
+    Ok(n.clamp(0.0, len as f64) as usize)
+}
+
+fn math(arguments: &[Object], name: &str, fun: fn(f64) -> f64) -> Result<Object, InterpretError> {
+    Ok(Object::Number(fun(number(&arguments[0], name)?)))
+}
+
+fn pow(_: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    let base = number(&arguments[0], "pow")?;
+    let exponent = number(&arguments[1], "pow")?;
+    Ok(Object::Number(base.powf(exponent)))
+}
+
+fn len(_: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    match arguments[0] {
+        Object::String(ref s) => Ok(Object::Integer(s.chars().count() as i64)),
+        Object::List(ref l) => Ok(Object::Integer(l.borrow().len() as i64)),
+        _ => Err(InterpretError::new(format!(
+            "Argument of `len` must be a string or a list, got `{}`.",
+            arguments[0]
+        ))),
+    }
+}
+
+fn substring(_: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    let chars: Vec<char> = string(&arguments[0], "substring")?.chars().collect();
+    let start = position(&arguments[1], chars.len(), "substring")?;
+    let end = position(&arguments[2], chars.len(), "substring")?.max(start);
+    Ok(Object::String(chars[start..end].iter().collect()))
+}
+
+fn index_of(_: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    let index = match arguments[0] {
+        Object::String(ref s) => {
+            let needle = string(&arguments[1], "indexOf")?;
+            s.find(needle.as_str()).map(|x| s[..x].chars().count())
+        }
+        Object::List(ref l) => l.borrow().iter().position(|x| *x == arguments[1]),
+        _ => {
+            return Err(InterpretError::new(format!(
+                "Argument of `indexOf` must be a string or a list, got `{}`.",
+                arguments[0]
+            )))
+        }
+    };
+    Ok(Object::Integer(index.map_or(-1, |x| x as i64)))
+}
+
+fn split(_: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    let s = string(&arguments[0], "split")?;
+    let separator = string(&arguments[1], "split")?;
+    let parts: Vec<Object> = if separator.is_empty() {
+        s.chars().map(|x| Object::String(x.to_string())).collect()
+    } else {
+        s.split(separator.as_str())
+            .map(|x| Object::String(x.to_string()))
+            .collect()
+    };
+    Ok(Object::List(Rc::new(RefCell::new(parts))))
+}
+
+fn parse_number(_: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    let s = string(&arguments[0], "parseNumber")?.trim();
+    if let Ok(n) = s.parse::<i64>() {
+        return Ok(Object::Integer(n));
+    }
+    Ok(s.parse::<f64>().map_or(Object::Nil, Object::Number))
+}
+
+fn list_push(_: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    list(&arguments[0], "push")?
+        .borrow_mut()
+        .push(arguments[1].clone());
+    Ok(Object::Nil)
+}
+
+fn list_pop(_: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    list(&arguments[0], "pop")?
+        .borrow_mut()
+        .pop()
+        .ok_or_else(|| InterpretError::new("Can't pop from an empty list."))
+}
+
+fn list_insert(_: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    let list = list(&arguments[0], "insert")?;
+    let len = list.borrow().len();
+    let index = position(&arguments[1], len, "insert")?;
+    list.borrow_mut().insert(index, arguments[2].clone());
+    Ok(Object::Nil)
+}
+
+fn list_remove(_: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    let list = list(&arguments[0], "remove")?;
+    let len = list.borrow().len();
+    let index = position(&arguments[1], len, "remove")?;
+    if index >= len {
+        return Err(InterpretError::new("Index out of range."));
+    }
+    let value = list.borrow_mut().remove(index);
+    Ok(value)
+}
+
+fn list_slice(_: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    let list = list(&arguments[0], "slice")?;
+    let list = list.borrow();
+    let start = position(&arguments[1], list.len(), "slice")?;
+    let end = position(&arguments[2], list.len(), "slice")?.max(start);
+    Ok(Object::List(Rc::new(RefCell::new(
+        list[start..end].to_vec(),
+    ))))
+}
+
+fn write(interpreter: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    let text = interpreter.stringify(&arguments[0])?;
+    interpreter.write(&text);
+    io::stdout().flush().ok();
+    Ok(Object::Nil)
+}
+
+fn read_line(_: &mut Interpreter, _: Vec<Object>) -> Result<Object, InterpretError> {
+    let mut line = String::new();
+    match io::stdin().read_line(&mut line) {
+        Ok(0) => Ok(Object::Nil),
+        Ok(_) => Ok(Object::String(
+            line.trim_end_matches(['\n', '\r']).to_string(),
+        )),
+        Err(err) => Err(InterpretError::new(format!("Can't read line: {}.", err))),
+    }
+}
+
+fn read_file(_: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    let path = string(&arguments[0], "readFile")?;
+    fs::read_to_string(path)
+        .map(Object::String)
+        .map_err(|err| InterpretError::new(format!("Can't read `{}`: {}.", path, err)))
+}
+
+fn write_file(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Object>,
+) -> Result<Object, InterpretError> {
+    let path = string(&arguments[0], "writeFile")?;
+    let content = interpreter.stringify(&arguments[1])?;
+    fs::write(path, content)
+        .map(|_| Object::Nil)
+        .map_err(|err| InterpretError::new(format!("Can't write `{}`: {}.", path, err)))
+}
@@ -1,17 +1,94 @@
-use crate::{expr::HashExpr, token::Token};
-use std::rc::Rc;
+use crate::{
+    expr::HashExpr,
+    token::{Token, TokenType},
+};
+use std::{fmt, rc::Rc};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Stmt {
     Expression(ExpressionStmt),
     Print(PrintStmt),
     Var(VarStmt),
+    Destructure(DestructureStmt),
     Block(BlockStmt),
     If(IfStmt),
     While(WhileStmt),
     Function(FunctionStmt),
     Return(ReturnStmt),
     Class(ClassStmt),
+    Trait(TraitStmt),
+    Throw(ThrowStmt),
+    Try(TryStmt),
+    Import(ImportStmt),
+    Test(TestStmt),
+}
+
+impl Stmt {
+    /// The line the statement starts on. Blocks have none, as their statements are what runs.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Stmt::Expression(v) => v.expression.expr.line(),
+            Stmt::Print(v) => Some(v.keyword.line),
+            Stmt::Var(v) => Some(v.name.line),
+            Stmt::Destructure(v) => Some(v.keyword.line),
+            Stmt::Block(_) => None,
+            Stmt::If(v) => v.condition.expr.line(),
+            Stmt::While(v) => v.condition.expr.line(),
+            Stmt::Function(v) => Some(v.name.line),
+            Stmt::Return(v) => Some(v.keyword.line),
+            Stmt::Class(v) => Some(v.name.line),
+            Stmt::Trait(v) => Some(v.name.line),
+            Stmt::Throw(v) => Some(v.keyword.line),
+            Stmt::Try(v) => Some(v.keyword.line),
+            Stmt::Import(v) => Some(v.keyword.line),
+            Stmt::Test(v) => Some(v.keyword.line),
+        }
+    }
+}
+
+impl fmt::Display for Stmt {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stmt::Expression(v) => v.fmt(fmt),
+            Stmt::Print(v) => v.fmt(fmt),
+            Stmt::Var(v) => v.fmt(fmt),
+            Stmt::Destructure(v) => v.fmt(fmt),
+            Stmt::Block(v) => v.fmt(fmt),
+            Stmt::If(v) => v.fmt(fmt),
+            Stmt::While(v) => v.fmt(fmt),
+            Stmt::Function(v) => v.fmt(fmt),
+            Stmt::Return(v) => v.fmt(fmt),
+            Stmt::Class(v) => v.fmt(fmt),
+            Stmt::Trait(v) => v.fmt(fmt),
+            Stmt::Throw(v) => v.fmt(fmt),
+            Stmt::Try(v) => v.fmt(fmt),
+            Stmt::Import(v) => v.fmt(fmt),
+            Stmt::Test(v) => v.fmt(fmt),
+        }
+    }
+}
+
+fn indent(text: &str) -> String {
+    let lines: Vec<String> = text.lines().map(|x| format!("  {}", x)).collect();
+    lines.join("\n")
+}
+
+/// Write `(head child...)` with every child on its own indented line.
+fn write_node<T: fmt::Display>(
+    fmt: &mut fmt::Formatter<'_>,
+    head: &str,
+    children: impl IntoIterator<Item = T>,
+) -> fmt::Result {
+    write!(fmt, "({}", head)?;
+    for child in children {
+        write!(fmt, "\n{}", indent(&child.to_string()))?;
+    }
+    write!(fmt, ")")
+}
+
+fn names(tokens: &[Rc<Token>]) -> String {
+    let names: Vec<&str> = tokens.iter().map(|x| x.lexeme.as_str()).collect();
+    names.join(" ")
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -25,26 +102,116 @@ impl ExpressionStmt {
     }
 }
 
+impl fmt::Display for ExpressionStmt {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "(; {})", self.expression)
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct PrintStmt {
+    pub keyword: Rc<Token>,
     pub expression: Rc<HashExpr>,
 }
 
 impl PrintStmt {
-    pub fn new(expression: Rc<HashExpr>) -> PrintStmt {
-        PrintStmt { expression }
+    pub fn new(keyword: Rc<Token>, expression: Rc<HashExpr>) -> PrintStmt {
+        PrintStmt {
+            keyword,
+            expression,
+        }
+    }
+}
+
+impl fmt::Display for PrintStmt {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "(print {})", self.expression)
     }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct VarStmt {
+    /// `var`, `let` or `const`.
+    pub keyword: Rc<Token>,
     pub name: Rc<Token>,
     pub initializer: Option<Rc<HashExpr>>,
 }
 
 impl VarStmt {
-    pub fn new(name: Rc<Token>, initializer: Option<Rc<HashExpr>>) -> VarStmt {
-        VarStmt { name, initializer }
+    pub fn new(keyword: Rc<Token>, name: Rc<Token>, initializer: Option<Rc<HashExpr>>) -> VarStmt {
+        VarStmt {
+            keyword,
+            name,
+            initializer,
+        }
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.keyword.token_type == TokenType::Const
+    }
+}
+
+impl fmt::Display for VarStmt {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keyword = &self.keyword.lexeme;
+        match self.initializer {
+            Some(ref initializer) => {
+                write!(fmt, "({} {} {})", keyword, self.name.lexeme, initializer)
+            }
+            None => write!(fmt, "({} {})", keyword, self.name.lexeme),
+        }
+    }
+}
+
+/// `var [a, b] = list;` or `var {x, y} = instance;`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DestructureStmt {
+    pub keyword: Rc<Token>,
+    /// The `[` of a list pattern or the `{` of a field pattern.
+    pub pattern: Rc<Token>,
+    pub names: Vec<Rc<Token>>,
+    pub initializer: Rc<HashExpr>,
+}
+
+impl DestructureStmt {
+    pub fn new(
+        keyword: Rc<Token>,
+        pattern: Rc<Token>,
+        names: Vec<Rc<Token>>,
+        initializer: Rc<HashExpr>,
+    ) -> DestructureStmt {
+        DestructureStmt {
+            keyword,
+            pattern,
+            names,
+            initializer,
+        }
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.keyword.token_type == TokenType::Const
+    }
+
+    pub fn is_list(&self) -> bool {
+        self.pattern.token_type == TokenType::LeftBracket
+    }
+}
+
+impl fmt::Display for DestructureStmt {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (open, close) = match self.is_list() {
+            true => ("[", "]"),
+            false => ("{", "}"),
+        };
+        write!(
+            fmt,
+            "({} {}{}{} {})",
+            self.keyword.lexeme,
+            open,
+            names(&self.names),
+            close,
+            self.initializer
+        )
     }
 }
 
@@ -59,6 +226,12 @@ impl BlockStmt {
     }
 }
 
+impl fmt::Display for BlockStmt {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_node(fmt, "block", &self.statements)
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct IfStmt {
     pub condition: Rc<HashExpr>,
@@ -80,6 +253,14 @@ impl IfStmt {
     }
 }
 
+impl fmt::Display for IfStmt {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let head = format!("if {}", self.condition);
+        let branches = [Some(&self.then_branch), self.else_branch.as_ref()];
+        write_node(fmt, &head, branches.into_iter().flatten())
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct WhileStmt {
     pub condition: Rc<HashExpr>,
@@ -92,6 +273,12 @@ impl WhileStmt {
     }
 }
 
+impl fmt::Display for WhileStmt {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_node(fmt, &format!("while {}", self.condition), [&self.body])
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct FunctionStmt {
     pub name: Rc<Token>,
@@ -105,6 +292,13 @@ impl FunctionStmt {
     }
 }
 
+impl fmt::Display for FunctionStmt {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let head = format!("fun {} ({})", self.name.lexeme, names(&self.params));
+        write_node(fmt, &head, &self.body)
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct ReturnStmt {
     pub keyword: Rc<Token>,
@@ -117,10 +311,162 @@ impl ReturnStmt {
     }
 }
 
+impl fmt::Display for ReturnStmt {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value {
+            Some(ref value) => write!(fmt, "(return {})", value),
+            None => write!(fmt, "(return)"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ThrowStmt {
+    pub keyword: Rc<Token>,
+    pub value: Rc<HashExpr>,
+}
+
+impl ThrowStmt {
+    pub fn new(keyword: Rc<Token>, value: Rc<HashExpr>) -> ThrowStmt {
+        ThrowStmt { keyword, value }
+    }
+}
+
+impl fmt::Display for ThrowStmt {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "(throw {})", self.value)
+    }
+}
+
+/// `test "name" { ... }`, only run when a test runner selects it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TestStmt {
+    pub keyword: Rc<Token>,
+    pub name: Rc<Token>,
+    pub body: Vec<Rc<Stmt>>,
+}
+
+impl TestStmt {
+    pub fn new(keyword: Rc<Token>, name: Rc<Token>, body: Vec<Rc<Stmt>>) -> TestStmt {
+        TestStmt {
+            keyword,
+            name,
+            body,
+        }
+    }
+}
+
+impl fmt::Display for TestStmt {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_node(fmt, &format!("test {}", self.name.lexeme), &self.body)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct CatchClause {
+    pub name: Rc<Token>,
+    pub body: Vec<Rc<Stmt>>,
+}
+
+impl CatchClause {
+    pub fn new(name: Rc<Token>, body: Vec<Rc<Stmt>>) -> CatchClause {
+        CatchClause { name, body }
+    }
+}
+
+impl fmt::Display for CatchClause {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_node(fmt, &format!("catch {}", self.name.lexeme), &self.body)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TryStmt {
+    pub keyword: Rc<Token>,
+    pub body: Vec<Rc<Stmt>>,
+    pub handler: Option<CatchClause>,
+    pub finally: Option<Vec<Rc<Stmt>>>,
+}
+
+impl TryStmt {
+    pub fn new(
+        keyword: Rc<Token>,
+        body: Vec<Rc<Stmt>>,
+        handler: Option<CatchClause>,
+        finally: Option<Vec<Rc<Stmt>>>,
+    ) -> TryStmt {
+        TryStmt {
+            keyword,
+            body,
+            handler,
+            finally,
+        }
+    }
+}
+
+struct Finally<'a>(&'a [Rc<Stmt>]);
+
+impl fmt::Display for Finally<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_node(fmt, "finally", self.0)
+    }
+}
+
+impl fmt::Display for TryStmt {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut children = vec![BlockStmt::new(self.body.clone()).to_string()];
+        if let Some(ref handler) = self.handler {
+            children.push(handler.to_string());
+        }
+        if let Some(ref finally) = self.finally {
+            children.push(format!("{}", Finally(finally)));
+        }
+        write_node(fmt, "try", children)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ImportStmt {
+    pub keyword: Rc<Token>,
+    pub path: Rc<Token>,
+    pub name: Option<Rc<Token>>,
+    pub names: Vec<Rc<Token>>,
+}
+
+impl ImportStmt {
+    pub fn new(
+        keyword: Rc<Token>,
+        path: Rc<Token>,
+        name: Option<Rc<Token>>,
+        names: Vec<Rc<Token>>,
+    ) -> ImportStmt {
+        ImportStmt {
+            keyword,
+            path,
+            name,
+            names,
+        }
+    }
+}
+
+impl fmt::Display for ImportStmt {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "(import {}", self.path.lexeme)?;
+        if let Some(ref name) = self.name {
+            write!(fmt, " {}", name.lexeme)?;
+        }
+        if !self.names.is_empty() {
+            write!(fmt, " ({})", names(&self.names))?;
+        }
+        write!(fmt, ")")
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct ClassStmt {
     pub name: Rc<Token>,
     pub superclass: Option<HashExpr>,
+    pub traits: Vec<HashExpr>,
     pub methods: Vec<FunctionStmt>,
 }
 
@@ -128,12 +474,46 @@ impl ClassStmt {
     pub fn new(
         name: Rc<Token>,
         superclass: Option<HashExpr>,
+        traits: Vec<HashExpr>,
         methods: Vec<FunctionStmt>,
     ) -> ClassStmt {
         ClassStmt {
             name,
             superclass,
+            traits,
             methods,
         }
     }
 }
+
+impl fmt::Display for ClassStmt {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut head = format!("class {}", self.name.lexeme);
+        if let Some(ref superclass) = self.superclass {
+            head.push_str(&format!(" (< {})", superclass));
+        }
+        if !self.traits.is_empty() {
+            let traits: Vec<String> = self.traits.iter().map(|x| x.to_string()).collect();
+            head.push_str(&format!(" (with {})", traits.join(" ")));
+        }
+        write_node(fmt, &head, &self.methods)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TraitStmt {
+    pub name: Rc<Token>,
+    pub methods: Vec<FunctionStmt>,
+}
+
+impl TraitStmt {
+    pub fn new(name: Rc<Token>, methods: Vec<FunctionStmt>) -> TraitStmt {
+        TraitStmt { name, methods }
+    }
+}
+
+impl fmt::Display for TraitStmt {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_node(fmt, &format!("trait {}", self.name.lexeme), &self.methods)
+    }
+}
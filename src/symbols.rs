@@ -0,0 +1,223 @@
+use crate::{
+    parser::parse,
+    resolver::Resolver,
+    scanner::scan_tokens,
+    stmt::{ClassStmt, FunctionStmt, Stmt},
+    token::{Token, TokenType},
+};
+use std::{collections::HashMap, rc::Rc};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolKind {
+    Variable,
+    Constant,
+    Parameter,
+    Function,
+    Method,
+    Class,
+    Trait,
+    Import,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: Rc<Token>,
+    pub kind: SymbolKind,
+    /// How the declaration reads in source, e.g. `fun add(a, b)`.
+    pub detail: String,
+    /// The function, class or trait this symbol is declared in.
+    pub parent: Option<usize>,
+}
+
+/// Every declaration of a script and every variable use resolved to one of them. Scopes are
+/// the resolver's: a use refers to the innermost declaration visible where it appears, and
+/// globals are visible everywhere.
+#[derive(Debug, Default)]
+pub struct SymbolIndex {
+    pub symbols: Vec<Symbol>,
+    /// Uses of a symbol, as the token naming it and the index of the symbol.
+    pub references: Vec<(Rc<Token>, usize)>,
+}
+
+/// Index the declarations of `code`, which must compile.
+pub fn index(code: &str) -> Result<SymbolIndex, String> {
+    let statements = parse(scan_tokens(code)?)?;
+    let mut resolver = Resolver::new();
+    resolver.resolve(&statements)?;
+
+    let mut indexer = Indexer {
+        index: SymbolIndex::default(),
+        parents: Vec::new(),
+    };
+    indexer.block(&statements);
+
+    let mut index = indexer.index;
+    let symbols: HashMap<*const Token, usize> = index
+        .symbols
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (Rc::as_ptr(&x.name), i))
+        .collect();
+    for (name, declaration) in resolver.uses() {
+        if let Some(symbol) = symbols.get(&Rc::as_ptr(declaration)) {
+            index.references.push((name.clone(), *symbol));
+        }
+    }
+    index.references.sort_by_key(|(x, _)| (x.line, x.column));
+
+    Ok(index)
+}
+
+impl SymbolIndex {
+    /// The symbol named by the token at `line` and `column`, both 1-based.
+    pub fn symbol_at(&self, line: usize, column: usize) -> Option<usize> {
+        let covers = |token: &Token| {
+            token.line == line
+                && token.column <= column
+                && column < token.column + token.lexeme.chars().count()
+        };
+
+        self.symbols
+            .iter()
+            .position(|x| covers(&x.name))
+            .or_else(|| {
+                self.references
+                    .iter()
+                    .find(|(token, _)| covers(token))
+                    .map(|(_, symbol)| *symbol)
+            })
+    }
+
+    pub fn references_to(&self, symbol: usize) -> Vec<Rc<Token>> {
+        self.references
+            .iter()
+            .filter(|(_, x)| *x == symbol)
+            .map(|(token, _)| token.clone())
+            .collect()
+    }
+}
+
+/// Collects the declarations of a script. Which declaration a use refers to is left to the
+/// resolver.
+struct Indexer {
+    index: SymbolIndex,
+    parents: Vec<usize>,
+}
+
+fn signature(keyword: &str, stmt: &FunctionStmt) -> String {
+    let params: Vec<&str> = stmt.params.iter().map(|x| x.lexeme.as_str()).collect();
+    format!("{}{}({})", keyword, stmt.name.lexeme, params.join(", "))
+}
+
+fn class_detail(stmt: &ClassStmt) -> String {
+    let mut detail = format!("class {}", stmt.name.lexeme);
+    if let Some(ref superclass) = stmt.superclass {
+        detail += &format!(" < {}", superclass);
+    }
+    if !stmt.traits.is_empty() {
+        let traits: Vec<String> = stmt.traits.iter().map(|x| x.to_string()).collect();
+        detail += &format!(" with {}", traits.join(", "));
+    }
+    detail
+}
+
+impl Indexer {
+    /// A name declared by `var`, `let` or `const`.
+    fn variable(&mut self, keyword: &Token, name: &Rc<Token>) {
+        let kind = match keyword.token_type {
+            TokenType::Const => SymbolKind::Constant,
+            _ => SymbolKind::Variable,
+        };
+        let detail = format!("{} {}", keyword.lexeme, name.lexeme);
+        self.declare(name, kind, detail);
+    }
+
+    fn declare(&mut self, name: &Rc<Token>, kind: SymbolKind, detail: String) -> usize {
+        self.index.symbols.push(Symbol {
+            name: name.clone(),
+            kind,
+            detail,
+            parent: self.parents.last().cloned(),
+        });
+        self.index.symbols.len() - 1
+    }
+
+    fn block(&mut self, statements: &[Rc<Stmt>]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn function(&mut self, symbol: usize, stmt: &FunctionStmt) {
+        self.parents.push(symbol);
+        for param in &stmt.params {
+            let detail = format!("(parameter) {}", param.lexeme);
+            self.declare(param, SymbolKind::Parameter, detail);
+        }
+        self.block(&stmt.body);
+        self.parents.pop();
+    }
+
+    fn methods(&mut self, owner: usize, methods: &[FunctionStmt]) {
+        let name = self.index.symbols[owner].name.lexeme.clone();
+        for method in methods {
+            self.parents.push(owner);
+            let detail = signature(&format!("{}.", name), method);
+            let symbol = self.declare(&method.name, SymbolKind::Method, detail);
+            self.parents.pop();
+            self.function(symbol, method);
+        }
+    }
+
+    fn statement(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expression(_) | Stmt::Print(_) | Stmt::Return(_) | Stmt::Throw(_) => {}
+            Stmt::Var(stmt) => self.variable(&stmt.keyword, &stmt.name),
+            Stmt::Destructure(stmt) => {
+                for name in &stmt.names {
+                    self.variable(&stmt.keyword, name);
+                }
+            }
+            Stmt::Block(stmt) => self.block(&stmt.statements),
+            Stmt::If(stmt) => {
+                self.statement(&stmt.then_branch);
+                if let Some(ref else_branch) = stmt.else_branch {
+                    self.statement(else_branch);
+                }
+            }
+            Stmt::While(stmt) => self.statement(&stmt.body),
+            Stmt::Function(stmt) => {
+                let symbol =
+                    self.declare(&stmt.name, SymbolKind::Function, signature("fun ", stmt));
+                self.function(symbol, stmt);
+            }
+            Stmt::Class(stmt) => {
+                let symbol = self.declare(&stmt.name, SymbolKind::Class, class_detail(stmt));
+                self.methods(symbol, &stmt.methods);
+            }
+            Stmt::Trait(stmt) => {
+                let detail = format!("trait {}", stmt.name.lexeme);
+                let symbol = self.declare(&stmt.name, SymbolKind::Trait, detail);
+                self.methods(symbol, &stmt.methods);
+            }
+            Stmt::Try(stmt) => {
+                self.block(&stmt.body);
+                if let Some(ref handler) = stmt.handler {
+                    let detail = format!("catch ({})", handler.name.lexeme);
+                    self.declare(&handler.name, SymbolKind::Variable, detail);
+                    self.block(&handler.body);
+                }
+                if let Some(ref finally) = stmt.finally {
+                    self.block(finally);
+                }
+            }
+            Stmt::Import(stmt) => {
+                let detail = format!("import {}", stmt.path.lexeme);
+                for name in stmt.name.iter().chain(&stmt.names) {
+                    self.declare(name, SymbolKind::Import, detail.clone());
+                }
+            }
+            Stmt::Test(stmt) => self.block(&stmt.body),
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{
+    debugger::describe,
+    function::Native,
+    interpreter::{Hook, InterpretError, Interpreter},
+    lox::{skip_out, Lox, LoxError},
+    object::Object,
+    parser::parse,
+    scanner::scan_tokens,
+    stmt::Stmt,
+    token::Token,
+};
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+pub fn natives() -> Vec<Native> {
+    vec![
+        Native::new("assert", 2, assert),
+        Native::new("assertEqual", 2, assert_equal),
+    ]
+}
+
+fn assert(_: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    if arguments[0].is_truthy() {
+        return Ok(Object::Nil);
+    }
+    Err(InterpretError::new(format!(
+        "Assertion failed: {}",
+        arguments[1]
+    )))
+}
+
+/// `assertEqual(actual, expected)`, comparing lists by their elements.
+fn assert_equal(_: &mut Interpreter, arguments: Vec<Object>) -> Result<Object, InterpretError> {
+    if same(&arguments[0], &arguments[1]) {
+        return Ok(Object::Nil);
+    }
+    Err(InterpretError::new(format!(
+        "Expected {} but got {}.",
+        describe(&arguments[1]),
+        describe(&arguments[0])
+    )))
+}
+
+fn same(a: &Object, b: &Object) -> bool {
+    match (a, b) {
+        (Object::List(a), Object::List(b)) => {
+            let (a, b) = (a.borrow(), b.borrow());
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| same(a, b))
+        }
+        (a, b) => a == b,
+    }
+}
+
+/// A test of a script: a `test "name" { ... }` block, or a top-level `fun test_name()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestCase {
+    pub name: String,
+    pub line: usize,
+    pub function: bool,
+}
+
+pub fn discover(code: &str) -> Result<Vec<TestCase>, String> {
+    let statements = parse(scan_tokens(code)?)?;
+
+    let mut tests = Vec::new();
+    for statement in &statements {
+        match statement.as_ref() {
+            Stmt::Test(stmt) => {
+                if let Object::String(ref name) = stmt.name.literal {
+                    tests.push(TestCase {
+                        name: name.clone(),
+                        line: stmt.keyword.line,
+                        function: false,
+                    });
+                }
+            }
+            Stmt::Function(stmt)
+                if stmt.name.lexeme.starts_with("test_") && stmt.params.is_empty() =>
+            {
+                tests.push(TestCase {
+                    name: stmt.name.lexeme.clone(),
+                    line: stmt.name.line,
+                    function: true,
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(tests)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    /// The script line that was running when the test failed.
+    pub line: Option<usize>,
+    pub message: String,
+    /// What the test printed.
+    pub output: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    pub file: String,
+    pub test: TestCase,
+    pub failure: Option<Failure>,
+}
+
+impl fmt::Display for TestResult {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(ref failure) = self.failure else {
+            return write!(fmt, "PASS {}: {}", self.file, self.test.name);
+        };
+
+        let line = failure.line.unwrap_or(self.test.line);
+        write!(
+            fmt,
+            "FAIL {}:{}: {}\n    {}",
+            self.file, line, self.test.name, failure.message
+        )?;
+        for line in failure.output.lines() {
+            write!(fmt, "\n    | {}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// The `.lox` files under `path`, or `path` itself when it is a file, in a stable order.
+pub fn files(path: &Path) -> io::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut found = Vec::new();
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)?
+        .map(|x| x.map(|x| x.path()))
+        .collect::<Result<_, _>>()?;
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() {
+            found.extend(files(&entry)?);
+        } else if entry.extension().is_some_and(|x| x == "lox") {
+            found.push(entry);
+        }
+    }
+    Ok(found)
+}
+
+/// Run every test of the script at `path`, each in an interpreter of its own.
+pub fn run_file(lox: &Lox, path: &Path) -> Result<Vec<TestResult>, LoxError> {
+    let code = fs::read_to_string(path)
+        .map_err(|err| LoxError::Runtime(format!("Can't read `{}`: {}.", path.display(), err)))?;
+    let code = skip_out(&code);
+    let file = path.display().to_string();
+
+    let mut names = HashSet::new();
+    let mut results = Vec::new();
+    for test in discover(code).map_err(LoxError::Compile)? {
+        let failure = if names.insert(test.name.clone()) {
+            run_test(lox, code, path, &test)?
+        } else {
+            Some(Failure {
+                line: Some(test.line),
+                message: format!("Duplicate test name '{}'.", test.name),
+                output: String::new(),
+            })
+        };
+        results.push(TestResult {
+            file: file.clone(),
+            test,
+            failure,
+        });
+    }
+    Ok(results)
+}
+
+fn run_test(
+    lox: &Lox,
+    code: &str,
+    path: &Path,
+    test: &TestCase,
+) -> Result<Option<Failure>, LoxError> {
+    let tracker = Rc::new(RefCell::new(LastLine::default()));
+    let output = Rc::new(RefCell::new(Vec::new()));
+
+    let mut interpreter = lox.interpreter();
+    interpreter.set_hook(tracker.clone());
+    interpreter.set_output(output.clone());
+    if !test.function {
+        interpreter.select_test(&test.name);
+    }
+
+    let mut result = lox.run_with(&mut interpreter, code, Some(path));
+    if result.is_ok() && test.function {
+        result = lox.run_with(&mut interpreter, &format!("{}();", test.name), Some(path));
+    }
+
+    match result {
+        Ok(()) => Ok(None),
+        Err(LoxError::Runtime(message)) => Ok(Some(Failure {
+            line: tracker.borrow().line,
+            message,
+            output: String::from_utf8_lossy(&output.borrow()).to_string(),
+        })),
+        Err(err) => Err(err),
+    }
+}
+
+/// Remembers the line of the statement of the script running now, which is where a failing
+/// assertion is. Lines of functions that already returned are forgotten.
+#[derive(Default)]
+struct LastLine {
+    line: Option<usize>,
+    callers: Vec<Option<usize>>,
+}
+
+impl Hook for LastLine {
+    fn statement(&mut self, interpreter: &Interpreter, line: usize) -> Result<(), InterpretError> {
+        if interpreter.in_script() {
+            self.line = Some(line);
+        }
+        Ok(())
+    }
+
+    fn enter(&mut self, _: &Interpreter, _: &Rc<Token>) {
+        self.callers.push(self.line);
+    }
+
+    fn exit(&mut self, _: &Interpreter, failed: bool) {
+        let caller = self.callers.pop();
+        // An error keeps the line it was raised on, a caught one is left by the next statement.
+        if let (Some(line), false) = (caller, failed) {
+            self.line = line;
+        }
+    }
+}
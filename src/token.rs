@@ -1,4 +1,5 @@
 use crate::object::Object;
+use std::fmt;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum TokenType {
@@ -7,6 +8,8 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
     Minus,
@@ -14,6 +17,11 @@ pub enum TokenType {
     Semicolon,
     Slash,
     Star,
+    Percent,
+    Ampersand,
+    Pipe,
+    Caret,
+    Colon,
 
     // One or two character tokens
     Bang,
@@ -24,39 +32,70 @@ pub enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
+    LessLess,
+    GreaterGreater,
+    Tilde,
+    TildeSlash,
+    StarStar,
+    PlusPlus,
+    MinusMinus,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PercentEqual,
+    Question,
+    QuestionQuestion,
+    QuestionDot,
 
     // Literals
     Identifier,
     String,
     Number,
+    // The text of a string before each `${`, the rest of the string being a `String`
+    Interpolation,
+
+    // Only kept for tools that need to see the source as written
+    Comment,
 
     // Keywords
     And,
+    Catch,
     Class,
+    Const,
     Else,
     False,
+    Finally,
     Fun,
     For,
     If,
+    Import,
+    Let,
     Nil,
     Or,
     Print,
     Return,
     Super,
     This,
+    Throw,
+    Trait,
     True,
+    Try,
     Var,
     While,
+    With,
 
     Eof,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(PartialEq, Clone)]
 pub struct Token {
     pub token_type: TokenType,
     pub lexeme: String,
     pub literal: Object,
     pub line: usize,
+    /// 1-based, 0 for tokens that don't come from source code.
+    pub column: usize,
 }
 
 impl Token {
@@ -66,6 +105,38 @@ impl Token {
             lexeme,
             literal,
             line,
+            column: 0,
+        }
+    }
+
+    pub fn with_column(mut self, column: usize) -> Token {
+        self.column = column;
+        self
+    }
+}
+
+// The column is left out, runtime error messages embed this representation.
+impl fmt::Debug for Token {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Token")
+            .field("token_type", &self.token_type)
+            .field("lexeme", &self.lexeme)
+            .field("literal", &self.literal)
+            .field("line", &self.line)
+            .finish()
+    }
+}
+
+/// One line of a token dump: position, type, lexeme and literal value.
+impl fmt::Display for Token {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let position = format!("{}:{}", self.line, self.column);
+        let token_type = format!("{:?}", self.token_type);
+        write!(fmt, "{:<8} {:<14} {}", position, token_type, self.lexeme)?;
+        match self.literal {
+            Object::Nil => Ok(()),
+            Object::String(ref s) => write!(fmt, " {:?}", s),
+            ref literal => write!(fmt, " {}", literal),
         }
     }
 }
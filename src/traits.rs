@@ -0,0 +1,32 @@
+use crate::{environment::Environment, stmt::FunctionStmt, token::Token};
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
+
+#[derive(Debug)]
+pub struct Trait {
+    pub name: Rc<Token>,
+    pub methods: HashMap<String, Rc<FunctionStmt>>,
+    pub closure: Rc<RefCell<Environment>>,
+    pub globals: Rc<RefCell<Environment>>,
+}
+
+impl Trait {
+    pub fn new(
+        name: Rc<Token>,
+        methods: HashMap<String, Rc<FunctionStmt>>,
+        closure: Rc<RefCell<Environment>>,
+        globals: Rc<RefCell<Environment>>,
+    ) -> Trait {
+        Trait {
+            name,
+            methods,
+            closure,
+            globals,
+        }
+    }
+}
+
+impl fmt::Display for Trait {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "<trait {}>", self.name.lexeme)
+    }
+}
@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod coverage_test {
+    use rlox::{
+        coverage::{Coverage, Recorder},
+        lox::Lox,
+    };
+    use std::{cell::RefCell, rc::Rc};
+
+    fn run(coverage: &mut Coverage, file: &str, code: &str) {
+        let recorder = Rc::new(RefCell::new(Recorder::new()));
+        Lox::new().run_hooked(code, None, recorder.clone()).unwrap();
+        coverage.add(file, code, &recorder.borrow()).unwrap();
+    }
+
+    #[test]
+    fn test_lcov() {
+        let code = "fun sign(n) {
+  if (n < 0) {
+    return -1;
+  }
+  return 1;
+}
+sign(1);";
+
+        let mut coverage = Coverage::new();
+        run(&mut coverage, "sign.lox", code);
+        let expected = "TN:
+SF:sign.lox
+DA:1,1
+DA:2,1
+DA:3,0
+DA:5,1
+DA:7,1
+LF:5
+LH:4
+end_of_record
+";
+        assert_eq!(coverage.to_lcov(), expected);
+
+        // A second run is added to the first, as is a report read back.
+        run(
+            &mut coverage,
+            "sign.lox",
+            "fun sign(n) {\n  if (n < 0) {\n    return -1;\n  }\n  return 1;\n}\nsign(-1);",
+        );
+        assert_eq!(coverage.files["sign.lox"][&3], 1);
+        assert_eq!(coverage.files["sign.lox"][&2], 2);
+
+        let mut merged = Coverage::from_lcov(&coverage.to_lcov()).unwrap();
+        merged.merge(&coverage);
+        assert_eq!(merged.files["sign.lox"][&2], 4);
+        assert!(Coverage::from_lcov("DA:1,1").is_err());
+    }
+}
@@ -0,0 +1,194 @@
+#[cfg(test)]
+mod dap_test {
+    use rlox::{dap::serve, json::Json};
+    use std::{
+        cell::RefCell,
+        io::{self, Cursor, Write},
+        rc::Rc,
+    };
+
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn frame(seq: usize, command: &str, arguments: &str) -> String {
+        let message = format!(
+            r#"{{"seq":{},"type":"request","command":"{}","arguments":{}}}"#,
+            seq, command, arguments
+        );
+        format!("Content-Length: {}\r\n\r\n{}", message.len(), message)
+    }
+
+    #[test]
+    fn test_session() {
+        let program = std::env::temp_dir().join("rlox_dap_test.lox");
+        std::fs::write(
+            &program,
+            "class Point {
+  init(x) {
+    this.x = x;
+  }
+}
+fun show(p) {
+  print p.x;
+}
+show(Point(7));
+",
+        )
+        .unwrap();
+        let path = program.to_string_lossy().replace('\\', "\\\\");
+
+        let input = [
+            frame(1, "initialize", "{}"),
+            frame(2, "launch", &format!(r#"{{"program":"{}"}}"#, path)),
+            frame(
+                3,
+                "setBreakpoints",
+                &format!(
+                    r#"{{"source":{{"path":"{}"}},"breakpoints":[{{"line":7}}]}}"#,
+                    path
+                ),
+            ),
+            frame(4, "configurationDone", "{}"),
+            frame(5, "stackTrace", r#"{"threadId":1}"#),
+            frame(6, "scopes", r#"{"frameId":0}"#),
+            frame(7, "variables", r#"{"variablesReference":1}"#),
+            frame(8, "variables", r#"{"variablesReference":3}"#),
+            frame(9, "continue", r#"{"threadId":1}"#),
+            frame(10, "disconnect", "{}"),
+        ]
+        .concat();
+
+        let output = Shared::default();
+        serve(Cursor::new(input), output.clone()).unwrap();
+        let output = String::from_utf8_lossy(&output.0.borrow()).to_string();
+        let messages: Vec<Json> = output
+            .split("Content-Length: ")
+            .filter(|x| !x.is_empty())
+            .map(|x| Json::parse(x.split_once("\r\n\r\n").unwrap().1).unwrap())
+            .collect();
+
+        let response = |command: &str| {
+            messages
+                .iter()
+                .find(|x| x.get("command").and_then(Json::as_str) == Some(command))
+                .and_then(|x| x.get("body"))
+                .unwrap()
+                .to_string()
+        };
+        let events: Vec<String> = messages
+            .iter()
+            .filter_map(|x| x.get("event").and_then(Json::as_str))
+            .map(String::from)
+            .collect();
+
+        assert_eq!(
+            events,
+            vec!["initialized", "stopped", "output", "exited", "terminated"]
+        );
+        assert_eq!(
+            response("setBreakpoints"),
+            r#"{"breakpoints":[{"verified":true,"line":7}]}"#
+        );
+
+        let stack = response("stackTrace");
+        assert!(stack.contains(r#""id":0,"name":"show","line":7"#));
+        assert!(stack.contains(r#""id":1,"name":"<script>","line":9"#));
+
+        assert!(response("scopes").starts_with(
+            r#"{"scopes":[{"name":"Locals","variablesReference":1,"expensive":false},{"name":"Globals""#
+        ));
+
+        // `p` is an instance, expanded under the next free reference.
+        let locals: Vec<String> = messages
+            .iter()
+            .filter(|x| x.get("command").and_then(Json::as_str) == Some("variables"))
+            .map(|x| x.get("body").unwrap().to_string())
+            .collect();
+        assert_eq!(
+            locals,
+            vec![
+                r#"{"variables":[{"name":"p","value":"<instance of Point>","variablesReference":3}]}"#,
+                r#"{"variables":[{"name":"x","value":"7","variablesReference":0}]}"#,
+            ]
+        );
+
+        let output = messages
+            .iter()
+            .find(|x| x.get("event").and_then(Json::as_str) == Some("output"))
+            .unwrap();
+        assert_eq!(
+            output.get("body").unwrap().get("output").unwrap().as_str(),
+            Some("7\n")
+        );
+    }
+
+    #[test]
+    fn test_disconnect_inside_try() {
+        let name = format!("rlox_dap_try_test_{}.lox", std::process::id());
+        let program = std::env::temp_dir().join(name);
+        std::fs::write(
+            &program,
+            "try {
+  print \"in try\";
+  print \"stopped\";
+} catch (e) {
+  print \"caught\";
+}
+print \"after\";
+",
+        )
+        .unwrap();
+        let path = program.to_string_lossy().replace('\\', "\\\\");
+
+        let input = [
+            frame(1, "initialize", "{}"),
+            frame(2, "launch", &format!(r#"{{"program":"{}"}}"#, path)),
+            frame(
+                3,
+                "setBreakpoints",
+                &format!(
+                    r#"{{"source":{{"path":"{}"}},"breakpoints":[{{"line":3}}]}}"#,
+                    path
+                ),
+            ),
+            frame(4, "configurationDone", "{}"),
+            frame(5, "pause", r#"{"threadId":1}"#),
+            frame(6, "disconnect", "{}"),
+        ]
+        .concat();
+
+        let output = Shared::default();
+        serve(Cursor::new(input), output.clone()).unwrap();
+        std::fs::remove_file(&program).unwrap();
+        let output = String::from_utf8_lossy(&output.0.borrow()).to_string();
+        let messages: Vec<Json> = output
+            .split("Content-Length: ")
+            .filter(|x| !x.is_empty())
+            .map(|x| Json::parse(x.split_once("\r\n\r\n").unwrap().1).unwrap())
+            .collect();
+
+        let pause = messages
+            .iter()
+            .find(|x| x.get("command").and_then(Json::as_str) == Some("pause"))
+            .unwrap();
+        assert_eq!(pause.get("success"), Some(&Json::Bool(false)));
+
+        // Disconnecting stops the script, which can't catch that.
+        let printed: Vec<&str> = messages
+            .iter()
+            .filter(|x| x.get("event").and_then(Json::as_str) == Some("output"))
+            .filter_map(|x| x.get("body")?.get("output")?.as_str())
+            .collect();
+        assert_eq!(printed, vec!["in try\n"]);
+    }
+}
@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod debugger_test {
+    use rlox::{
+        debugger::Debugger,
+        lox::{Lox, LoxError},
+    };
+    use std::{cell::RefCell, io::Cursor, rc::Rc};
+
+    fn debug(code: &str, commands: &str) -> String {
+        let debugger = Rc::new(RefCell::new(Debugger::new(
+            code,
+            Cursor::new(commands.to_string()),
+            Vec::new(),
+        )));
+        Lox::new().run_hooked(code, None, debugger.clone()).unwrap();
+        let output = String::from_utf8_lossy(debugger.borrow().output()).to_string();
+        output
+    }
+
+    #[test]
+    fn test_breakpoint_and_stepping() {
+        let code = "class Point {
+  init(x) {
+    this.x = x;
+  }
+}
+fun twice(n) {
+  var result = n * 2;
+  return result;
+}
+var p = Point(1);
+print twice(p.x);
+print \"done\";";
+
+        let output = debug(code, "b 8\nc\nbt\nenv\no\np p\nc\n");
+        let expected = "[line 1] in <script>: class Point {
+(debug) Breakpoint at line 8.
+(debug) [line 8] in twice: return result;
+(debug) #0 twice [line 8]
+#1 <script> [line 11]
+(debug) #0 n = 1, result = 2
+#1 (globals) Point = <class Point>, args = [], p = <instance of Point>, twice = <fun twice>
+(debug) [line 12] in <script>: print \"done\";
+(debug) p = <instance of Point>
+  x = 1
+(debug) ";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_step_into_and_over() {
+        let code = "fun f() {
+  return 1;
+}
+f();
+f();";
+        let output = debug(code, "n\ns\nn\nn\nq\n");
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "[line 1] in <script>: fun f() {",
+                "(debug) [line 4] in <script>: f();",
+                "(debug) [line 2] in f: return 1;",
+                "(debug) [line 5] in <script>: f();",
+                "(debug) ",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quit_inside_try() {
+        let code = "try {
+  print \"in try\";
+  print \"not reached\";
+} catch (e) {
+  print \"caught\";
+} finally {
+  print \"finally\";
+}
+print \"after\";";
+        let debugger = Rc::new(RefCell::new(Debugger::new(
+            code,
+            Cursor::new("s\ns\nq\n".to_string()),
+            Vec::new(),
+        )));
+        let output = Rc::new(RefCell::new(Vec::new()));
+
+        let lox = Lox::new();
+        let mut interpreter = lox.interpreter();
+        interpreter.set_hook(debugger);
+        interpreter.set_output(output.clone());
+        let result = lox.run_with(&mut interpreter, code, None);
+
+        // Quitting is not an error the script can catch.
+        assert_eq!(
+            result,
+            Err(LoxError::Runtime("Stopped by the debugger.".to_string()))
+        );
+        assert_eq!(String::from_utf8_lossy(&output.borrow()), "in try\n");
+    }
+}
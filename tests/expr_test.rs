@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod expr_test {
-    use rlox::{expr::*, object::*, token::*};
+    use rlox::{expr::*, object::*, parser::parse, scanner::scan_tokens, token::*};
     use std::rc::Rc;
 
     #[test]
@@ -25,4 +25,68 @@ mod expr_test {
 
         assert_eq!(expression.to_string(), "(* (- 123) (45.67))".to_string());
     }
+
+    #[test]
+    fn test_print_statements() {
+        let code = "class A < B { get() { return this.x[0]; } }\nif (a or b) print f(1, \"s\"); else a.y = nil;";
+        let statements = parse(scan_tokens(code).unwrap()).unwrap();
+        let printed: Vec<String> = statements.iter().map(|x| x.to_string()).collect();
+
+        assert_eq!(
+            printed.join("\n"),
+            [
+                "(class A (< B)",
+                "  (fun get ()",
+                "    (return ([] (. this x) 0))))",
+                "(if (or a b)",
+                "  (print (call f 1 \"s\"))",
+                "  (; (= (. a y) nil)))",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_token_columns() {
+        let tokens = scan_tokens("var a = 1;\n  print a;").unwrap();
+        let positions: Vec<(usize, usize)> = tokens.iter().map(|x| (x.line, x.column)).collect();
+
+        assert_eq!(
+            positions,
+            vec![
+                (1, 1),
+                (1, 5),
+                (1, 7),
+                (1, 9),
+                (1, 10),
+                (2, 3),
+                (2, 9),
+                (2, 10),
+                (2, 11)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_number_literals() {
+        let tokens = scan_tokens("12 0x1F 0b101 0o17 1_000 1.5 2e3 1_0.2_5").unwrap();
+        let literals: Vec<Object> = tokens.iter().map(|x| x.literal.clone()).collect();
+
+        assert_eq!(
+            format!("{:?}", &literals[..8]),
+            "[Integer(12), Integer(31), Integer(5), Integer(15), Integer(1000), \
+             Number(1.5), Number(2000.0), Number(10.25)]"
+        );
+        for code in [
+            "1_",
+            "1__0",
+            "0x",
+            "1e",
+            "123abc",
+            "123.",
+            "99999999999999999999",
+        ] {
+            assert!(scan_tokens(code).is_err(), "{}", code);
+        }
+    }
 }
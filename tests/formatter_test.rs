@@ -0,0 +1,49 @@
+#[cfg(test)]
+mod formatter_test {
+    use rlox::formatter::format;
+
+    #[test]
+    fn test_format() {
+        let code = "var a=-1; // neg\nif(a<0)print -a;else{print a;}\n\n\n// lone\nclass A<B{init(x){this.x=x[0];}}\nfor(var i=0;i<3;i=i+1){}\n";
+        let expected = "var a = -1; // neg\nif (a < 0) print -a;\nelse {\n    print a;\n}\n\n// lone\nclass A < B {\n    init(x) {\n        this.x = x[0];\n    }\n}\nfor (var i = 0; i < 3; i = i + 1) {}\n";
+
+        let formatted = format(code).unwrap();
+        assert_eq!(formatted, expected);
+        assert_eq!(format(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_format_interpolation() {
+        let code = "print \"a ${ x+1 } b ${\"${y}\"}\";";
+        let expected = "print \"a ${x + 1} b ${\"${y}\"}\";\n";
+        assert_eq!(format(code).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_format_operators() {
+        let code = "a+=1;b[0] --;print ++ c-d++ - -e**2;";
+        let expected = "a += 1;\nb[0]--;\nprint ++c - d++ - -e ** 2;\n";
+        assert_eq!(format(code).unwrap(), expected);
+
+        // Without the space, `- -x` would become a decrement.
+        let code = "print - -x;print - --x;print -(-x);";
+        let expected = "print - -x;\nprint - --x;\nprint -(-x);\n";
+        assert_eq!(format(code).unwrap(), expected);
+
+        let code = "print a?b:c??d?.e;";
+        let expected = "print a ? b : c ?? d?.e;\n";
+        assert_eq!(format(code).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_format_destructuring() {
+        let code = "var [a,b]=pair();const {x,y}=point;[a,b]=[b,a];";
+        let expected = "var [a, b] = pair();\nconst { x, y } = point;\n[a, b] = [b, a];\n";
+        assert_eq!(format(code).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_format_invalid() {
+        assert!(format("print (1;").is_err());
+    }
+}
@@ -0,0 +1,73 @@
+#[cfg(test)]
+mod json_test {
+    use rlox::{json::Json, lox::Lox};
+
+    #[test]
+    fn test_escape() {
+        let value = Json::object(vec![
+            ("text", Json::string("a \"b\"\n\\")),
+            (
+                "items",
+                Json::Array(vec![Json::Null, Json::Bool(true), Json::Number(1.5)]),
+            ),
+        ]);
+
+        assert_eq!(
+            value.to_string(),
+            r#"{"text":"a \"b\"\n\\","items":[null,true,1.5]}"#
+        );
+    }
+
+    #[test]
+    fn test_ast_json() {
+        let json = Lox::new()
+            .ast_json("var a = 1;\n{ var b = a; print b; }")
+            .unwrap();
+
+        let statements = json.get("statements").unwrap().as_array().unwrap();
+        assert_eq!(statements[0].get("type").unwrap().as_str(), Some("Var"));
+
+        let block = statements[1].get("statements").unwrap().as_array().unwrap();
+        let print = block[1].get("expression").unwrap();
+        assert_eq!(print.get("type").unwrap().as_str(), Some("Variable"));
+
+        let name = print.get("name").unwrap();
+        assert_eq!(name.get("line").unwrap().as_f64(), Some(2.0));
+        assert_eq!(name.get("column").unwrap().as_f64(), Some(20.0));
+
+        // `b` is declared in the same scope, `a` is a global.
+        let id = print.get("id").unwrap().clone();
+        assert_eq!(id.as_f64(), Some(3.0));
+        let locals = json.get("locals").unwrap().as_array().unwrap();
+        let local = locals.iter().find(|x| x.get("id") == Some(&id)).unwrap();
+        assert_eq!(local.get("depth").unwrap().as_f64(), Some(0.0));
+    }
+
+    #[test]
+    fn test_ast_json_numbers() {
+        let json = Lox::new()
+            .ast_json("print 9007199254740993;\nprint 2.0;")
+            .unwrap();
+
+        let statements = json.get("statements").unwrap().as_array().unwrap();
+        let value = |i: usize| {
+            let expression = statements[i].get("expression").unwrap();
+            expression.get("value").unwrap().clone()
+        };
+        let integer = value(0);
+        assert_eq!(
+            integer.get("integer").unwrap().as_str(),
+            Some("9007199254740993")
+        );
+        assert_eq!(value(1).as_f64(), Some(2.0));
+    }
+
+    #[test]
+    fn test_ast_json_stable() {
+        let lox = Lox::new();
+        let code = "fun f(x) { return x + 1; }\nprint f(2);";
+        let first = lox.ast_json(code).unwrap().to_string();
+        lox.ast_json("var unrelated = [1, 2, 3];").unwrap();
+        assert_eq!(lox.ast_json(code).unwrap().to_string(), first);
+    }
+}
@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod linter_test {
+    use rlox::linter::lint;
+
+    fn codes(code: &str) -> Vec<(usize, &'static str)> {
+        lint(code)
+            .unwrap()
+            .into_iter()
+            .map(|x| (x.line, x.lint.code))
+            .collect()
+    }
+
+    #[test]
+    fn test_lints() {
+        let code = "var g = 1;
+fun f(a, b, _c) {
+  var unused = 1;
+  var g = 2;
+  print g;
+  undeclared = 3;
+  \"text\"(1);
+  return a;
+  print b;
+}
+class A {
+  helper() { return 1; }
+  get() { return this.x; }
+}";
+
+        assert_eq!(
+            codes(code),
+            vec![
+                (3, "L001"),
+                (4, "L004"),
+                (6, "L005"),
+                (7, "L006"),
+                (9, "L003"),
+                (12, "L007"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_suppression() {
+        let code = "fun f(x) {} // lint-ignore: unused-parameter
+// lint-ignore
+fun g(y) {}
+fun h(z) {} // lint-ignore: L001";
+
+        assert_eq!(codes(code), vec![(4, "L002")]);
+    }
+
+    #[test]
+    fn test_unreachable_suppression() {
+        let code = "fun f() {
+  return 1;
+  1 + 2; // lint-ignore: L003
+}
+fun g() {
+  throw \"error\";
+  print 3;
+}";
+
+        assert_eq!(codes(code), vec![(7, "L003")]);
+    }
+
+    #[test]
+    fn test_shadowing_builtins() {
+        let code = "var g = 1;
+fun f() {
+  var clock = 1;
+  var Error = 2;
+  var g = 3;
+  print clock + Error + g;
+}";
+
+        assert_eq!(codes(code), vec![(5, "L004")]);
+    }
+
+    #[test]
+    fn test_undeclared_update() {
+        let code = "var count = 0;
+count += 1;
+nope += 1;
+nope++;
+--nope;";
+
+        assert_eq!(codes(code), vec![(3, "L005"), (4, "L005"), (5, "L005")]);
+        assert!(lint("const A = 1; A += 1;").is_err());
+        assert!(lint("const A = 1; A++;").is_err());
+    }
+
+    #[test]
+    fn test_compile_error() {
+        assert!(lint("var a = 1; { var a = a; }").is_err());
+    }
+}
@@ -1,97 +1,334 @@
-#![feature(internal_output_capture)]
-
 pub mod test_utils;
 
 #[cfg(test)]
 mod lox_test {
-    use crate::test_utils::{default_filter, TraverseIterator};
-    use rlox::lox;
-    use std::{fs::File, io::Read, panic, sync::Arc};
+    use crate::test_utils::{default_filter, FilterMode, TraverseIterator};
+    use regex::Regex;
+    use rlox::lox::Lox;
+    use std::{
+        cell::RefCell,
+        env, fs,
+        path::{Path, PathBuf},
+        rc::Rc,
+    };
 
-    #[test]
-    fn test_lox_scripts() {
-        let iterator = TraverseIterator::new(
-            "/Users/bytedance/Desktop/rlox/tests/scripts",
-            &default_filter,
-        );
-        for path in iterator {
-            let mut file = File::open(&path).unwrap();
-            let mut script = String::new();
-            file.read_to_string(&mut script).unwrap();
+    #[derive(Debug, PartialEq)]
+    enum Stderr {
+        Empty,
+        Exact(String),
+        /// The message of an error, after the location rlox puts in front of it.
+        EndsWith(String),
+    }
 
-            let parts: Vec<&str> = script.split("------ output ------").collect();
-            if parts.len() > 1 {
-                std::io::set_output_capture(Some(Default::default()));
+    /// What a script is expected to print and how it should end.
+    ///
+    /// Scripts either end with a `------ output ------` or `------ error ------` section, or
+    /// carry the annotations of the craftinginterpreters test suite on their lines:
+    /// `// expect: value` for each printed line, `// expect runtime error: message`, and
+    /// `// Error at 'x': message` or `// [line 3] Error ...` for compile errors.
+    #[derive(Debug, PartialEq)]
+    struct Expectation {
+        stdout: Vec<String>,
+        stderr: Stderr,
+        /// `None` when the script should fail without saying whether it compiles.
+        exit_code: Option<i32>,
+    }
 
-                let result = panic::catch_unwind(|| {
-                    lox::run_code(parts[0]);
+    impl Expectation {
+        /// Under `strict`, errors are expected in the format of jlox.
+        fn parse(script: &str, strict: bool) -> Option<Expectation> {
+            if let Some((_, output)) = script.split_once("------ output ------") {
+                return Some(Expectation {
+                    stdout: output.trim().lines().map(|x| x.to_string()).collect(),
+                    stderr: Stderr::Empty,
+                    exit_code: Some(0),
                 });
+            }
+            if let Some((_, error)) = script.split_once("------ error ------") {
+                return Some(Expectation {
+                    stdout: Vec::new(),
+                    stderr: Stderr::Exact(error.trim().to_string()),
+                    exit_code: None,
+                });
+            }
 
-                let captured = std::io::set_output_capture(None);
-
-                if let Err(err) = result {
-                    if let Some(captured) = err.downcast_ref::<&str>() {
-                        assert!(false, "assertion failed: {}\n{}", path.display(), captured);
-                        continue;
-                    }
-                    if let Some(captured) = err.downcast_ref::<String>() {
-                        assert!(false, "assertion failed: {}\n{}", path.display(), captured);
-                        continue;
-                    }
-                    assert!(
-                        false,
-                        "assertion failed: {}\n{}",
-                        path.display(),
-                        "Unknown error."
-                    );
+            let line_error = Regex::new(r"// \[(?:java )?line (\d+)\] (Error.*)").unwrap();
+            let mut expectation = Expectation {
+                stdout: Vec::new(),
+                stderr: Stderr::Empty,
+                exit_code: Some(0),
+            };
+            let mut compile_errors = Vec::new();
+            let mut annotated = false;
+            for (i, line) in script.lines().enumerate() {
+                if let Some((_, value)) = line.split_once("// expect: ") {
+                    expectation.stdout.push(value.to_string());
+                } else if let Some((_, message)) = line.split_once("// expect runtime error: ") {
+                    let message = message.trim().to_string();
+                    expectation.stderr = match strict {
+                        true => Stderr::Exact(format!("{}\n[line {}]", message, i + 1)),
+                        false => Stderr::EndsWith(message),
+                    };
+                    expectation.exit_code = Some(70);
+                } else if let Some(captures) = line_error.captures(line) {
+                    compile_errors.push(format!("[line {}] {}", &captures[1], &captures[2]));
+                } else if let Some((_, error)) = line.split_once("// Error") {
+                    compile_errors.push(format!("[line {}] Error{}", i + 1, error));
+                } else {
                     continue;
                 }
+                annotated = true;
+            }
 
-                let captured = captured.unwrap();
-                let captured = Arc::try_unwrap(captured).unwrap();
-                let captured = captured.into_inner().unwrap();
-                let captured = String::from_utf8(captured).unwrap();
+            if !compile_errors.is_empty() {
+                expectation.stderr = Stderr::Exact(compile_errors.join("\n"));
+                expectation.exit_code = Some(65);
+            }
+            annotated.then_some(expectation)
+        }
 
-                assert_eq!(captured.trim(), parts[1].trim(), "{}", path.display());
-                continue;
+        /// Describe how a run differs from the expectation, or `None` when it matches.
+        fn check(&self, stdout: &str, stderr: &str, exit_code: i32) -> Option<String> {
+            let mut problems = Vec::new();
+
+            // An `------ error ------` section says nothing of what was printed before.
+            let printed: Vec<&str> = stdout.trim_end().lines().collect();
+            if self.exit_code.is_some() && printed != self.stdout {
+                problems.push(format!("stdout differs:\n{}", diff(&self.stdout, &printed)));
             }
 
-            let parts: Vec<&str> = script.split("------ error ------").collect();
-            if parts.len() > 1 {
-                std::io::set_output_capture(Some(Default::default()));
+            let stderr = stderr.trim();
+            let stderr_matches = match self.stderr {
+                Stderr::Empty => stderr.is_empty(),
+                Stderr::Exact(ref expected) => stderr == expected,
+                Stderr::EndsWith(ref expected) => stderr.ends_with(expected.as_str()),
+            };
+            if !stderr_matches {
+                let expected = match self.stderr {
+                    Stderr::Exact(ref x) | Stderr::EndsWith(ref x) => x.as_str(),
+                    Stderr::Empty => "",
+                };
+                problems.push(format!("stderr differs:\n- {}\n+ {}", expected, stderr));
+            }
 
-                let result = panic::catch_unwind(|| {
-                    lox::run_code(parts[0]);
-                });
+            let code_matches = match self.exit_code {
+                Some(expected) => expected == exit_code,
+                None => exit_code != 0,
+            };
+            if !code_matches {
+                let expected = self
+                    .exit_code
+                    .map_or("an error".to_string(), |x| x.to_string());
+                problems.push(format!("exit code {}, expected {}", exit_code, expected));
+            }
 
-                std::io::set_output_capture(None);
+            (!problems.is_empty()).then(|| problems.join("\n"))
+        }
+    }
 
-                if let Err(err) = result {
-                    if let Some(captured) = err.downcast_ref::<&str>() {
-                        assert_eq!(captured.trim(), parts[1].trim(), "{}", path.display());
-                        continue;
+    fn diff(expected: &[String], actual: &[&str]) -> String {
+        let mut lines = Vec::new();
+        for i in 0..expected.len().max(actual.len()) {
+            match (expected.get(i), actual.get(i)) {
+                (Some(a), Some(b)) if a == b => lines.push(format!("  {}", a)),
+                (a, b) => {
+                    if let Some(a) = a {
+                        lines.push(format!("- {}", a));
                     }
-                    if let Some(captured) = err.downcast_ref::<String>() {
-                        assert_eq!(captured.trim(), parts[1].trim(), "{}", path.display());
-                        continue;
+                    if let Some(b) = b {
+                        lines.push(format!("+ {}", b));
                     }
-                    assert!(
-                        false,
-                        "assertion failed: {}\n{}",
-                        path.display(),
-                        "Unknown error."
-                    );
-                    continue;
                 }
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// Run a script with its output captured, returning stdout, stderr and the exit code.
+    fn run(lox: &Lox, path: &Path, script: &str) -> (String, String, i32) {
+        let code = rlox::lox::skip_out(script);
+        let output = Rc::new(RefCell::new(Vec::new()));
+
+        let mut interpreter = lox.interpreter();
+        interpreter.set_output(output.clone());
+        let (stderr, exit_code) = match lox.run_with(&mut interpreter, code, Some(path)) {
+            Ok(()) => (String::new(), 0),
+            Err(err) => (err.to_string(), err.exit_code()),
+        };
 
-                assert!(
-                    false,
-                    "assertion failed: {}\n{}",
-                    path.display(),
-                    "Expected error but nothing."
-                );
+        let stdout = String::from_utf8_lossy(&output.borrow()).to_string();
+        (stdout, stderr, exit_code)
+    }
+
+    /// Check every annotated script of `paths`, returning how many were checked and how the
+    /// failing ones failed.
+    fn check_scripts(
+        root: &Path,
+        paths: Vec<PathBuf>,
+        strict: impl Fn(&Path) -> bool,
+    ) -> (usize, Vec<String>) {
+        let mut failures = Vec::new();
+        let mut checked = 0;
+        for path in paths {
+            let script = fs::read_to_string(&path).unwrap();
+            let name = path.strip_prefix(root).unwrap_or(&path);
+            let strict = strict(name);
+            // Modules imported by other scripts expect nothing themselves.
+            let Some(expectation) = Expectation::parse(&script, strict) else {
                 continue;
+            };
+            checked += 1;
+
+            let lox = match strict {
+                true => Lox::new().strict(),
+                false => Lox::new(),
+            };
+            let (stdout, stderr, exit_code) = run(&lox, &path, &script);
+            if let Some(problem) = expectation.check(&stdout, &stderr, exit_code) {
+                failures.push(format!("{}: {}", name.display(), problem));
             }
         }
+        (checked, failures)
+    }
+
+    #[test]
+    fn test_lox_scripts() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/scripts");
+        let mut paths: Vec<_> =
+            TraverseIterator::new(root.to_str().unwrap(), &default_filter).collect();
+        paths.sort();
+
+        // Scripts in the style of the reference test suite run in strict mode.
+        let (checked, failures) = check_scripts(&root, paths, |x| x.starts_with("jlox"));
+        assert!(checked > 0, "No scripts found in {}.", root.display());
+        assert!(
+            failures.is_empty(),
+            "{} of {} scripts failed:\n\n{}",
+            failures.len(),
+            checked,
+            failures.join("\n\n")
+        );
+    }
+
+    /// Tests of the reference suite that jlox itself skips: those of the chapters before the
+    /// interpreter is complete, the limits of clox, and NaN equality, which Java gets wrong.
+    const SUITE_SKIPPED: [&str; 10] = [
+        "benchmark",
+        "scanning",
+        "expressions",
+        "limit/loop_too_large.lox",
+        "limit/no_reuse_constants.lox",
+        "limit/too_many_constants.lox",
+        "limit/too_many_locals.lox",
+        "limit/too_many_upvalues.lox",
+        "limit/stack_overflow.lox",
+        "number/nan_equality.lox",
+    ];
+
+    /// The `test` directory of a craftinginterpreters checkout, at `LOX_SUITE` or else
+    /// cloned into `target/craftinginterpreters`.
+    fn suite_dir() -> Option<PathBuf> {
+        let checkout = match env::var_os("LOX_SUITE") {
+            Some(path) => PathBuf::from(path),
+            None => Path::new(env!("CARGO_MANIFEST_DIR")).join("target/craftinginterpreters"),
+        };
+        let dir = checkout.join("test");
+        dir.is_dir().then_some(dir)
+    }
+
+    #[test]
+    fn test_craftinginterpreters_suite() {
+        let Some(root) = suite_dir() else {
+            eprintln!(
+                "Skipped the reference suite: clone \
+                 https://github.com/munificent/craftinginterpreters into \
+                 target/craftinginterpreters, or point LOX_SUITE at a checkout."
+            );
+            return;
+        };
+
+        let filter = |path: &Path| {
+            let name = path.strip_prefix(&root).unwrap_or(path);
+            let skipped = SUITE_SKIPPED.iter().any(|x| name == Path::new(x));
+            match path.is_dir() {
+                true => (FilterMode::Exclude, skipped),
+                false => (
+                    FilterMode::Include,
+                    !skipped && path.extension().is_some_and(|x| x == "lox"),
+                ),
+            }
+        };
+        let mut paths: Vec<_> = TraverseIterator::new(root.to_str().unwrap(), &filter).collect();
+        paths.sort();
+
+        let (checked, failures) = check_scripts(&root, paths, |_| true);
+        assert!(checked > 0, "No tests found in {}.", root.display());
+        assert!(
+            failures.is_empty(),
+            "{} of {} tests of the reference suite failed:\n\n{}",
+            failures.len(),
+            checked,
+            failures.join("\n\n")
+        );
+    }
+
+    #[test]
+    fn test_const_global_across_runs() {
+        let lox = Lox::new();
+        let path = Path::new("repl.lox");
+        let mut interpreter = lox.interpreter();
+        lox.run_with(&mut interpreter, "const A = 1;", Some(path))
+            .unwrap();
+
+        // Each run is resolved on its own, the environment still knows `A` is a constant.
+        let err = lox
+            .run_with(&mut interpreter, "A = 2;", Some(path))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "[line 1] : Can't assign to constant `A`.");
+        assert_eq!(err.exit_code(), 70);
+
+        lox.run_with(&mut interpreter, "var A = 2; A = 3;", Some(path))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_parse_annotations() {
+        let script = "print 1; // expect: 1
+print \"a\" + 1; // expect runtime error: Operands must be two numbers or two strings.";
+        assert_eq!(
+            Expectation::parse(script, false),
+            Some(Expectation {
+                stdout: vec!["1".to_string()],
+                stderr: Stderr::EndsWith(
+                    "Operands must be two numbers or two strings.".to_string()
+                ),
+                exit_code: Some(70),
+            })
+        );
+        assert_eq!(
+            Expectation::parse(script, true).unwrap().stderr,
+            Stderr::Exact("Operands must be two numbers or two strings.\n[line 2]".to_string())
+        );
+        assert_eq!(Expectation::parse("print 1;", false), None);
+
+        let script = "print 1 +; // Error at ';': Expect expression.
+// [java line 3] Error at 'b': Expect ';' after value.";
+        let expectation = Expectation::parse(script, true).unwrap();
+        assert_eq!(
+            expectation.stderr,
+            Stderr::Exact(
+                "[line 1] Error at ';': Expect expression.
+[line 3] Error at 'b': Expect ';' after value."
+                    .to_string()
+            )
+        );
+        assert_eq!(expectation.exit_code, Some(65));
+
+        let expectation = Expectation::parse("print 1;\n------ output ------\n1\n", false).unwrap();
+        assert_eq!(expectation.check("1\n", "", 0), None);
+        assert_eq!(
+            expectation.check("2\n", "", 0),
+            Some("stdout differs:\n- 1\n+ 2".to_string())
+        );
     }
 }
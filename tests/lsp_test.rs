@@ -0,0 +1,122 @@
+#[cfg(test)]
+mod lsp_test {
+    use rlox::{json::Json, lsp::serve, symbols::index};
+    use std::io::Cursor;
+
+    fn frame(message: &str) -> String {
+        format!("Content-Length: {}\r\n\r\n{}", message.len(), message)
+    }
+
+    // Responses and notifications written by the server, in order.
+    fn messages(output: &[u8]) -> Vec<Json> {
+        let output = String::from_utf8_lossy(output);
+        output
+            .split("Content-Length: ")
+            .filter(|x| !x.is_empty())
+            .map(|x| Json::parse(x.split_once("\r\n\r\n").unwrap().1).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_index() {
+        let code = "var a = 1;
+fun f(a) {
+  return a;
+}
+print f(a);";
+        let index = index(code).unwrap();
+
+        // The parameter shadows the global inside `f`.
+        let param = index.symbol_at(2, 7).unwrap();
+        assert_eq!(index.symbol_at(3, 10), Some(param));
+        let global = index.symbol_at(1, 5).unwrap();
+        assert_eq!(index.symbol_at(5, 9), Some(global));
+        assert_ne!(param, global);
+
+        let f = index.symbol_at(5, 7).unwrap();
+        assert_eq!(index.symbols[f].detail, "fun f(a)");
+        assert_eq!(index.symbols[param].parent, Some(f));
+        assert_eq!(index.references_to(global).len(), 1);
+    }
+
+    #[test]
+    fn test_session() {
+        let text = "class A {\\n  get() { return 1; }\\n}\\nvar x = A();\\nprint x;\\n";
+        let input = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#.to_string(),
+            format!(
+                r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"file:///a.lox","text":"{}"}}}}}}"#,
+                text
+            ),
+            r#"{"jsonrpc":"2.0","id":2,"method":"textDocument/definition","params":{"textDocument":{"uri":"file:///a.lox"},"position":{"line":4,"character":6}}}"#.to_string(),
+            r#"{"jsonrpc":"2.0","id":3,"method":"textDocument/documentSymbol","params":{"textDocument":{"uri":"file:///a.lox"}}}"#.to_string(),
+            r#"{"jsonrpc":"2.0","method":"textDocument/didChange","params":{"textDocument":{"uri":"file:///a.lox"},"contentChanges":[{"text":"print ;"}]}}"#.to_string(),
+            r#"{"jsonrpc":"2.0","id":4,"method":"shutdown"}"#.to_string(),
+            r#"{"jsonrpc":"2.0","method":"exit"}"#.to_string(),
+        ]
+        .iter()
+        .map(|x| frame(x))
+        .collect::<String>();
+
+        let mut output = Vec::new();
+        let code = serve(&mut Cursor::new(input), &mut output).unwrap();
+        assert_eq!(code, 0);
+
+        let messages = messages(&output);
+        assert_eq!(messages.len(), 6);
+
+        let capabilities = messages[0]
+            .get("result")
+            .unwrap()
+            .get("capabilities")
+            .unwrap();
+        assert_eq!(capabilities.get("hoverProvider"), Some(&Json::Bool(true)));
+
+        // `get` never uses `this`, which is only worth a hint.
+        let diagnostics = messages[1]
+            .get("params")
+            .unwrap()
+            .get("diagnostics")
+            .unwrap();
+        assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+        assert_eq!(
+            diagnostics.as_array().unwrap()[0].get("severity"),
+            Some(&Json::Number(3.0))
+        );
+
+        assert_eq!(
+            messages[2].get("result").unwrap().to_string(),
+            r#"{"uri":"file:///a.lox","range":{"start":{"line":3,"character":4},"end":{"line":3,"character":5}}}"#
+        );
+
+        let symbols = messages[3].get("result").unwrap().as_array().unwrap();
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].get("name").unwrap().as_str(), Some("A"));
+        let children = symbols[0].get("children").unwrap().as_array().unwrap();
+        assert_eq!(children[0].get("detail").unwrap().as_str(), Some("A.get()"));
+
+        let diagnostics = messages[4]
+            .get("params")
+            .unwrap()
+            .get("diagnostics")
+            .unwrap();
+        let error = &diagnostics.as_array().unwrap()[0];
+        assert_eq!(error.get("severity"), Some(&Json::Number(1.0)));
+        // The range already says where, so the message is only the text of the error.
+        assert_eq!(
+            error.get("message").unwrap().as_str(),
+            Some("Unexpected token.")
+        );
+
+        assert_eq!(messages[5].get("result"), Some(&Json::Null));
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let json = Json::parse(r#"{"a": [1, -2.5e1, true, null], "b": "é\n😀"}"#).unwrap();
+        assert_eq!(json.get("a").unwrap().to_string(), "[1,-25,true,null]");
+        assert_eq!(json.get("b").unwrap().as_str(), Some("é\n😀"));
+        assert!(Json::parse("[1,").is_err());
+        assert!(Json::parse("{} x").is_err());
+    }
+}
@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod profiler_test {
+    use rlox::{lox::Lox, profiler::Profiler};
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn test_profile() {
+        let code = "fun fib(n) {
+  if (n < 2) return n;
+  return fib(n - 1) + fib(n - 2);
+}
+class A {
+  init() {}
+}
+A();
+A();
+fib(4);";
+
+        let profiler = Rc::new(RefCell::new(Profiler::new()));
+        Lox::new().run_hooked(code, None, profiler.clone()).unwrap();
+        let mut profiler = profiler.borrow_mut();
+        profiler.finish();
+
+        assert_eq!(profiler.functions["fib:1"].calls, 9);
+        assert_eq!(profiler.functions["init:6"].calls, 2);
+        assert_eq!(profiler.functions["<script>"].calls, 1);
+        let fib = &profiler.functions["fib:1"];
+        assert!(fib.exclusive <= fib.inclusive);
+
+        // The `if` on line 2 runs on every call, its `return` on the 5 leaves.
+        let lines: Vec<(usize, usize)> = profiler.lines.iter().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(
+            lines,
+            vec![(1, 1), (2, 14), (3, 4), (5, 1), (8, 1), (9, 1), (10, 1)]
+        );
+
+        let stacks: Vec<String> = profiler
+            .collapsed()
+            .lines()
+            .map(|x| x.rsplit_once(' ').unwrap().0.to_string())
+            .collect();
+        assert_eq!(
+            stacks,
+            vec![
+                "<script>",
+                "<script>;fib:1",
+                "<script>;fib:1;fib:1",
+                "<script>;fib:1;fib:1;fib:1",
+                "<script>;fib:1;fib:1;fib:1;fib:1",
+                "<script>;init:6",
+            ]
+        );
+        assert!(profiler.report().starts_with("function"));
+    }
+}
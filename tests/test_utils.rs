@@ -78,7 +78,7 @@ mod test_utils {
 
     #[test]
     fn test_traverse() {
-        let iterator = TraverseIterator::new("/Users/bytedance/Desktop/rlox", &default_filter);
+        let iterator = TraverseIterator::new(env!("CARGO_MANIFEST_DIR"), &default_filter);
         for path in iterator {
             println!("{}", path.display());
         }
@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod testing_test {
+    use rlox::{lox::Lox, testing};
+
+    #[test]
+    fn test_run_file() {
+        let name = format!("rlox_testing_test_{}.lox", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(
+            &path,
+            "fun add(a, b) { return a + b; }
+
+test \"adds\" {
+  assertEqual(add(1, 2), 3);
+}
+
+test \"fails\" {
+  print \"shown on failure\";
+  assert(add(1, 1) == 3, \"one and one\");
+}
+
+fun test_function() {
+  assertEqual(add(1, 1), \"2\");
+}
+
+test \"adds\" {}
+",
+        )
+        .unwrap();
+
+        let results = testing::run_file(&Lox::new(), &path);
+        std::fs::remove_file(&path).unwrap();
+        let results = results.unwrap();
+        let names: Vec<&str> = results.iter().map(|x| x.test.name.as_str()).collect();
+        assert_eq!(names, vec!["adds", "fails", "test_function", "adds"]);
+        assert!(results[0].failure.is_none());
+
+        let failure = results[1].failure.as_ref().unwrap();
+        assert_eq!(failure.line, Some(9));
+        assert_eq!(
+            failure.message,
+            "[line 9] <Token { token_type: RightParen, lexeme: \")\", literal: Nil, line: 9 }> : Assertion failed: one and one"
+        );
+        assert_eq!(failure.output, "shown on failure\n");
+
+        // The failing line is the assertion's, not the one of `add` called before it.
+        let failure = results[2].failure.as_ref().unwrap();
+        assert_eq!(failure.line, Some(13));
+        assert_eq!(
+            failure.message,
+            "[line 13] <Token { token_type: RightParen, lexeme: \")\", literal: Nil, line: 13 }> : Expected \"2\" but got 2."
+        );
+
+        let failure = results[3].failure.as_ref().unwrap();
+        assert_eq!(failure.message, "Duplicate test name 'adds'.");
+        assert_eq!(
+            results[3].to_string(),
+            format!(
+                "FAIL {}:16: adds\n    Duplicate test name 'adds'.",
+                path.display()
+            )
+        );
+    }
+}